@@ -1,19 +1,32 @@
-use core::{fmt};
-use std::{collections::HashMap};
+use core::{fmt, ops::BitAndAssign};
+use std::{collections::{HashMap, HashSet}, io::{self, Write}, str::FromStr, sync::Arc, time::Duration};
 use colored::Colorize;
-use once_cell::sync::Lazy;
-use rand::random;
-use ux::u42;
+use once_cell::sync::OnceCell;
+use rayon::prelude::*;
 
-use crate::{api::search::Search, gui::play::{input_difficulty, play}};
+use crate::{api::{record::GameRecord, rules::{Rules, Standard}, search::Search, tt::{allocate, Slot}}, gui::play::{input_difficulty, play}};
 
-const EMPTY_BOARD:  u42 = u42::new(0);
+// size of the standard board, used by Game::new
+pub const HEIGHT: i32 = 6;
+pub const WIDTH: i32 = 7;
 
-// const U42_ONE:      u42 = u42::new(1);
-const U42_LASTBIT:  u42 = u42::new(0x20000000000);
+// columns are played with a single digit
+pub const MAX_WIDTH: i32 = 9;
 
-const HEIGHT: i32 = 6;
-const WIDTH: i32 = 7;
+// tokens to align to win, unless set with Dimensions::with_connect
+pub const CONNECT: i32 = 4;
+
+// squares of the largest board, the zobrist table has a key per color for each of them
+pub const MAX_SQUARES: i32 = 64;
+
+/// One bit per square of the board, see [Dimensions] for the layout.
+pub type Bitboard = u128;
+
+// seed of the Zobrist keys unless set_zobrist_seed is called first
+const ZOBRIST_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+// thinking time given to the AI in the CLI
+const THINK_TIME: Duration = Duration::from_secs(3);
 
 #[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
 pub enum Color {
@@ -26,7 +39,7 @@ impl Color {
     pub fn to_int(self) -> i32 {
         self as i32
     }
-    
+
     pub fn from_int(value: u64) -> Option<Self> {
         match value {
             0 => Some(Color::Red),
@@ -52,77 +65,163 @@ impl fmt::Display for Color {
     }
 }
 
-#[derive(Clone, Copy)]
-pub enum File {
-    A = 0x810204081,
-    B = 0x1020408102,
-    C = 0x2040810204,
-    D = 0x4081020408,
-    E = 0x8102040810,
-    F = 0x10204081020,
-    G = 0x20408102040,
+/// Width and height of a board and the number of tokens to align, every mask of the bitboards is derived from them.
+///
+/// Squares are numbered column by column from the bottom left, each column having an always empty sentinel bit
+/// above its top row: `(col, row)` is bit `col * (height + 1) + row`. An alignment crossing the edge of the board
+/// goes through a sentinel, so alignments are found with plain shifts, see [Dimensions::offset].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Dimensions {
+    pub width: i32,
+    pub height: i32,
+    pub connect: i32,
+    bottom: Bitboard, // one bit for the bottom row of each column
+    full: Bitboard
 }
 
-impl File {
-    pub fn mask(self) -> u42 {
-        u42::new(self as u64)
+impl Default for Dimensions {
+    fn default() -> Self {
+        Self::new(WIDTH, HEIGHT).unwrap()
     }
+}
 
-    pub fn mask_unchecked(col: i32) -> u42 {
-        match col {
-            0 => File::A.mask(),
-            1 => File::B.mask(),
-            2 => File::C.mask(),
-            3 => File::D.mask(),
-            4 => File::E.mask(),
-            5 => File::F.mask(),
-            6 => File::G.mask(),
-            _ => panic!("invalid col")
+impl Dimensions {
+    /// Returns `None` if the board is wider than [MAX_WIDTH] columns or has more than [MAX_SQUARES] squares.
+    pub fn new(width: i32, height: i32) -> Option<Self> {
+        // the height is bounded first so that the product can't overflow
+        if !(1..=MAX_WIDTH).contains(&width) || !(1..=MAX_SQUARES).contains(&height) || width * height > MAX_SQUARES {
+            return None;
         }
+
+        let bottom: Bitboard = (0..width).fold(0, |mask, col| mask | 1 << (col * (height + 1)));
+        // the columns are `height + 1` bits apart, so the multiplication can't carry from one column to the next
+        let full = bottom * ((1 << height) - 1);
+        Some(Self { width, height, connect: CONNECT, bottom, full })
     }
-}
 
-impl fmt::Display for File {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            File::A => write!(f, "Base 2 (binary): {:042b}", File::A as u64),
-            File::B => write!(f, "Base 2 (binary): {:042b}", File::B as u64),
-            File::C => write!(f, "Base 2 (binary): {:042b}", File::C as u64),
-            File::D => write!(f, "Base 2 (binary): {:042b}", File::D as u64),
-            File::E => write!(f, "Base 2 (binary): {:042b}", File::E as u64),
-            File::F => write!(f, "Base 2 (binary): {:042b}", File::F as u64),
-            File::G => write!(f, "Base 2 (binary): {:042b}", File::G as u64),
+    /// Sets the number of tokens to align to win, returns `None` if it is less than 2 or no alignment fits on the board.
+    pub fn with_connect(self, connect: i32) -> Option<Self> {
+        if connect < 2 || connect > self.width.max(self.height) {
+            return None;
         }
+        Some(Self { connect, ..self })
+    }
+
+    pub fn squares(self) -> i32 {
+        self.width * self.height
+    }
+
+    /// Bits between two consecutive columns, the height and the sentinel row.
+    pub fn stride(self) -> i32 {
+        self.height + 1
+    }
+
+    /// Whether the squares and sentinels fit in a `u64`, such as on the standard board.
+    pub fn fits_u64(self) -> bool {
+        self.width * self.stride() <= 64
+    }
+
+    /// Returns the mask of every square of the board, sentinels excluded.
+    pub fn full(self) -> Bitboard {
+        self.full
+    }
+
+    pub fn bit(self, col: i32, row: i32) -> Bitboard {
+        1 << (col * self.stride() + row)
+    }
+
+    pub fn bottom_row(self) -> Bitboard {
+        self.bottom
+    }
+
+    pub fn column_mask(self, col: i32) -> Bitboard {
+        ((1 << self.height) - 1) << (col * self.stride())
+    }
+
+    /// Returns the square right above the top token of the column, its sentinel when the column is full.
+    pub fn above_top(self, bitboard: Bitboard, col: i32) -> Bitboard {
+        // the carry of adding the bottom square to the column stops on its first empty square,
+        // on a u64 when the board fits in one as every push and undo goes through here
+        if self.fits_u64() {
+            let shift = col * self.stride();
+            let column = (self.column_mask(0) as u64) << shift;
+            return ((bitboard as u64 & column) + (1 << shift)) as Bitboard;
+        }
+        (bitboard & self.column_mask(col)) + self.bit(col, 0)
     }
-}
 
-impl TryFrom<i32> for File {
-    type Error = ();
+    /// Returns the column of the same square on the mirrored board.
+    pub fn mirror_col(self, col: i32) -> i32 {
+        self.width - 1 - col
+    }
+
+    /// Distance of the column to the center, in half columns so that it's exact on even widths.
+    pub fn center_distance(self, col: i32) -> i32 {
+        (2 * col - (self.width - 1)).abs()
+    }
 
-    fn try_from(v: i32) -> Result<Self, Self::Error> {
-        match v {
-            0 => Ok(File::A),
-            1 => Ok(File::B),
-            2 => Ok(File::C),
-            3 => Ok(File::D),
-            4 => Ok(File::E),
-            5 => Ok(File::F),
-            6 => Ok(File::G),
-            _ => Err(()),
+    /// Bits between two squares following each other in the direction, see [DIRECTIONS].
+    pub fn offset(self, (dx, dy): (i32, i32)) -> i32 {
+        dx * self.stride() + dy
+    }
+
+    /// Moves every square of `bitboard` to the mirrored column.
+    pub fn mirror_bits(self, bitboard: Bitboard) -> Bitboard {
+        let mut result = 0;
+        for col in 0..self.width {
+            let file = bitboard & self.column_mask(col);
+            result |= (file >> (col * self.stride())) << (self.mirror_col(col) * self.stride());
         }
+        result
+    }
+}
+
+/// Shifts the bits to the left, or to the right for a negative amount, every bit is dropped past the width of a [Bitboard].
+fn shift_bits(bitboard: Bitboard, amount: i32) -> Bitboard {
+    let shifted = if amount >= 0 { bitboard.checked_shl(amount as u32) } else { bitboard.checked_shr(amount.unsigned_abs()) };
+    shifted.unwrap_or(0)
+}
+
+/// Integer holding the squares of a board, a [Bitboard] or a `u64` on the boards that fit in one.
+trait Bits: Copy + PartialEq + BitAndAssign {
+    const ZERO: Self;
+
+    /// Shifts the bits to the right, every bit is dropped past the width of the integer.
+    fn shr_or_zero(self, amount: u32) -> Self;
+}
+
+impl Bits for u64 {
+    const ZERO: Self = 0;
+
+    fn shr_or_zero(self, amount: u32) -> Self {
+        self.checked_shr(amount).unwrap_or(0)
+    }
+}
+
+impl Bits for Bitboard {
+    const ZERO: Self = 0;
+
+    fn shr_or_zero(self, amount: u32) -> Self {
+        self.checked_shr(amount).unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Dimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
     }
 }
 
 /// The Board struct only represent the board states and hisotry of all token bits played with their respective color.
-/// 
+///
 /// Any logic related to interacting with the board is in [Game].
 #[derive(Default, Clone)]
 pub struct Board {
+    pub dims: Dimensions,
     // keeping track of global board to check for valid moves
-    pub bitboard: u42, // board is 7 col x 6 rows, same encoding as a chess board; (0, 0) is bottom left, going to right, then up
-    pub color_bitboard: u42,
-    pub history: Vec<(u42, Color)>, // just keep the flipped bit in history
-    heights: [i32; 7]
+    pub bitboard: Bitboard, // one bit per square, see [Dimensions]
+    pub color_bitboard: Bitboard,
+    pub history: Vec<PlayedMove>
 }
 
 impl Board {
@@ -132,223 +231,815 @@ impl Board {
         }
     }
 
+    pub fn with_dimensions(dims: Dimensions) -> Self {
+        Self {
+            dims,
+            ..Default::default()
+        }
+    }
+
     pub fn display_board(&self) {
-        // let binary_board = format!("{:042b}", self.bitboard);
-        // println!("{}", binary_board);
-        
-        let (red, yellow): (u42, u42) = match self.history.len() & 1 {
+        let (red, yellow) = match self.history.len() & 1 {
             0 => (self.color_bitboard, self.color_bitboard ^ self.bitboard),
             1 => (self.color_bitboard ^ self.bitboard, self.color_bitboard),
             _ => unreachable!(),
         };
 
-        for row in (0..HEIGHT).rev() {
-            for col in 0..WIDTH {
-                let index = row * WIDTH + col;
-                if u42::new(1 << index) & red != EMPTY_BOARD {
+        for row in (0..self.dims.height).rev() {
+            for col in 0..self.dims.width {
+                let bit = self.dims.bit(col, row);
+                if bit & red != 0 {
                     print!("{} ", "R".red());
                 }
-                else if u42::new(1 << index) & yellow != EMPTY_BOARD {
+                else if bit & yellow != 0 {
                     print!("{} ", "Y".yellow());
                 }
                 else {
-                    print!("{} ", '·');
+                    print!("· ");
                 }
             }
             println!();
         }
 
-        for i in 1..8 {
+        for i in 1..=self.dims.width {
             print!("{} ", i);
         }
         println!("\n");
     }
+
+    /// Returns the board flipped left to right, history included.
+    pub fn mirrored(&self) -> Board {
+        Board {
+            dims: self.dims,
+            bitboard: self.dims.mirror_bits(self.bitboard),
+            color_bitboard: self.dims.mirror_bits(self.color_bitboard),
+            history: self.history.iter().map(|&played| PlayedMove { mv: played.mv.mirrored(self.dims.width), ..played }).collect()
+        }
+    }
+}
+
+/// A move of the history of a [Board], with the color of the player who made it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayedMove {
+    pub mv: Move,
+    pub color: Color,
+    pub returned: bool // for a pop, whether the token went back on top of its column
+}
+
+/// A move in the 1-based column notation used by Connect Four databases and solvers, `4` being the center column.
+/// PopOut moves removing the bottom token of a column are prefixed by `p`, such as `p4`.
+///
+/// [Game] works internally with 0-based columns, use [Move::col] and [Move::from_col] to convert.
+/// Searches number the moves from 0 to `2 * width`, see [Move::index].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Move {
+    col: i32,
+    pop: bool
+}
+
+impl Move {
+    pub fn from_col(col: i32) -> Self {
+        Move { col, pop: false }
+    }
+
+    pub fn pop(col: i32) -> Self {
+        Move { col, pop: true }
+    }
+
+    pub fn col(self) -> i32 {
+        self.col
+    }
+
+    pub fn is_pop(self) -> bool {
+        self.pop
+    }
+
+    /// Number of the move among the moves of a board of the given width: the column for a push, `width + column` for a pop.
+    pub fn index(self, width: i32) -> i32 {
+        if self.pop { width + self.col } else { self.col }
+    }
+
+    pub fn from_index(index: i32, width: i32) -> Self {
+        if index >= width { Move::pop(index - width) } else { Move::from_col(index) }
+    }
+
+    /// Returns the same move on the mirrored board of the given width.
+    pub fn mirrored(self, width: i32) -> Self {
+        Move { col: width - 1 - self.col, ..self }
+    }
+
+    /// Parses a sequence of moves such as `44p53`, one digit per move.
+    pub fn parse_sequence(moves: &str) -> Result<Vec<Move>, MoveError> {
+        let mut result = Vec::new();
+        let mut pop = false;
+        for c in moves.chars() {
+            if c == 'p' && !pop {
+                pop = true;
+                continue;
+            }
+
+            let mv = Move::try_from(c)?;
+            result.push(Move { pop, ..mv });
+            pop = false;
+        }
+
+        match pop {
+            true => Err(MoveError::InvalidNotation('p')),
+            false => Ok(result)
+        }
+    }
+}
+
+impl TryFrom<char> for Move {
+    type Error = MoveError;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c.to_digit(10) {
+            Some(digit) if digit > 0 => Ok(Move::from_col(digit as i32 - 1)),
+            _ => Err(MoveError::InvalidNotation(c))
+        }
+    }
 }
 
+impl FromStr for Move {
+    type Err = MoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let moves = Move::parse_sequence(s)?;
+        match moves[..] {
+            [mv] => Ok(mv),
+            _ => Err(MoveError::InvalidNotation(s.chars().last().unwrap_or(' ')))
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pop {
+            write!(f, "p")?;
+        }
+        write!(f, "{}", self.col + 1)
+    }
+}
+
+/// Reasons for [Game::try_play] to reject a move, columns are 0-based but displayed in [Move] notation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    OutOfRange(i32),
+    ColumnFull(i32),
+    InvalidPush(i32), // forbidden by the rules
+    InvalidPop(i32), // forbidden by the rules, or the bottom token of the column isn't one of the player
+    GameOver,
+    InvalidNotation(char)
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::OutOfRange(col) => write!(f, "column {} is out of range", Move::from_col(*col)),
+            MoveError::ColumnFull(col) => write!(f, "column {} is full", Move::from_col(*col)),
+            MoveError::InvalidPush(col) => write!(f, "can't push in column {}", Move::from_col(*col)),
+            MoveError::InvalidPop(col) => write!(f, "can't pop from column {}", Move::from_col(*col)),
+            MoveError::GameOver => write!(f, "the game is already over"),
+            MoveError::InvalidNotation(c) => write!(f, "'{}' is not a valid move", c)
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// State of a [Game], updated after every move.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum GameOutcome {
+    #[default]
+    Ongoing,
+    Win(Color),
+    Draw
+}
+
+impl GameOutcome {
+    pub fn is_over(self) -> bool {
+        self != GameOutcome::Ongoing
+    }
+}
+
+// directions of an alignment: horizontal, vertical and both diagonals
+pub const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
 /// Game allows both player to interact with the [Board], while initiliazing and keeping tracks of the zobrist key for [Search]'s transposition table.
-#[derive(Default, Clone)]
+///
+/// Legality of the moves and the end of the game are delegated to its [Rules].
+#[derive(Clone)]
 pub struct Game {
     pub board: Board,
     pub turn_color: Color,
-    pub winner: Option<Color>,
-    pub zobrist_key: u64
+    pub outcome: GameOutcome,
+    pub zobrist_key: u64,
+    mirror_key: u64, // zobrist key of the mirrored position, kept up to date once tracked
+    track_mirror: bool,
+    zobrist: &'static Zobrist,
+    pub rules: Arc<dyn Rules>,
+    pub setup_len: usize // moves of the history played by the rules before the players
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self {
+            board: Board::default(),
+            turn_color: Color::default(),
+            outcome: GameOutcome::default(),
+            zobrist_key: 0,
+            mirror_key: 0,
+            track_mirror: false,
+            zobrist: Zobrist::table(),
+            rules: Arc::new(Standard),
+            setup_len: 0
+        }
+    }
 }
 
 impl Game {
+    /// Creates a game on the standard 7x6 board.
     pub fn new() -> Self {
         Self {
             ..Default::default()
         }
     }
 
+    pub fn with_dimensions(dims: Dimensions) -> Self {
+        Self {
+            board: Board::with_dimensions(dims),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a game following the rules, on a board of the given dimensions unless the rules impose theirs.
+    pub fn with_rules<R: Rules + 'static>(rules: R, dims: Dimensions) -> Self {
+        Self::with_shared_rules(Arc::new(rules), dims)
+    }
+
+    pub fn with_shared_rules(rules: Arc<dyn Rules>, dims: Dimensions) -> Self {
+        let mut game = Self {
+            board: Board::with_dimensions(rules.dimensions(dims)),
+            rules: rules.clone(),
+            ..Default::default()
+        };
+        rules.setup(&mut game);
+        game.setup_len = game.board.history.len();
+        game
+    }
+
+    pub fn dims(&self) -> Dimensions {
+        self.board.dims
+    }
+
+    /// Number of tokens in the column.
+    pub fn height(&self, col: i32) -> i32 {
+        (self.board.bitboard & self.dims().column_mask(col)).count_ones() as i32
+    }
+
+    /// Restarts the game on a board of the same size and rules.
     pub fn reset(&mut self) {
-        *self = Default::default();
+        *self = Self::with_shared_rules(self.rules.clone(), self.dims());
     }
 
-    /// Returns a `Vec<i32>` of possible columns to play
-    pub fn get_possible_moves(&self) -> Vec<i32> {
-        let mut result = Vec::new();
-        result.reserve_exact(WIDTH as usize);
+    /// Returns the color of the winner, if the game has been won.
+    pub fn winner(&self) -> Option<Color> {
+        match self.outcome {
+            GameOutcome::Win(color) => Some(color),
+            _ => None
+        }
+    }
 
-        if self.winner.is_some() {
-            return result;
+    /// Returns a `Vec<i32>` of possible columns to play, followed by the pops allowed by the rules numbered like [Move::index].
+    pub fn get_possible_moves(&self) -> Vec<i32> {
+        if self.outcome.is_over() {
+            return Vec::new();
         }
 
-        let mut origin = U42_LASTBIT;
-        for i in (0..WIDTH).rev() {
-            if origin & self.board.bitboard == EMPTY_BOARD {
-                result.push(i);
-            }
-            origin >>= 1;
+        if self.rules.push_only() {
+            return self.columns(self.playable_squares());
         }
-        result
+
+        let width = self.dims().width;
+        let mut moves: Vec<i32> = (0..width).rev().filter(|&col| self.rules.can_push(self, col)).collect();
+        moves.extend((0..width).rev().filter(|&col| self.rules.can_pop(self, col)).map(|col| width + col));
+        moves
     }
 
-    /// Function helper to push a new token into the board.
-    fn push(bitboard: &mut u42, color_bitboard: &mut u42, col: i32, history: &mut Vec<(u42, Color)>, token_color: Color, zobrist_key: &mut u64, heights: &mut [i32; 7]) {
-        let new_bit = u42::new(1u64 << (col + heights[col as usize] * WIDTH));
-        heights[col as usize] += 1;
+    /// Function helper to push a new token into the board, returns the row of the token.
+    fn push(board: &mut Board, col: i32, token_color: Color) -> i32 {
+        let new_bit = board.dims.above_top(board.bitboard, col);
 
-        *color_bitboard ^= *bitboard;
-        *bitboard ^= new_bit;
-        history.push((new_bit, token_color));
-        *zobrist_key ^= ZOBRIST_TABLE.token_square[Zobrist::get_index((new_bit, token_color)) as usize];
+        board.color_bitboard ^= board.bitboard;
+        board.bitboard ^= new_bit;
+        board.history.push(PlayedMove { mv: Move::from_col(col), color: token_color, returned: false });
+        new_bit.trailing_zeros() as i32 - col * board.dims.stride()
     }
-    
+
     /// We are assuming the input column has already been validated and must has been validated first.
     /// Will call [`Self::push()`].
     pub fn make_push(&mut self, col: i32) {
-        Self::push(&mut self.board.bitboard, &mut self.board.color_bitboard, col, &mut self.board.history, self.turn_color, &mut self.zobrist_key, &mut self.board.heights);
-        self.winner = self.check_win(); 
+        let row = Self::push(&mut self.board, col, self.turn_color);
+        self.toggle_token_key(col, row, self.turn_color);
+        if self.board.bitboard == self.dims().full() && self.rules.fills_board_first() {
+            self.toggle_key(self.zobrist.phase);
+        }
+        self.turn_color = self.turn_color.toggle();
+        self.outcome = self.rules.outcome(self);
+    }
+
+    /// Removes the bottom token of the side to move from the column, the tokens above fall down by one row.
+    /// Depending on the rules, the token then goes back on top of the column.
+    /// We are assuming the pop has been validated first, see [`Self::validate_pop()`].
+    ///
+    /// When the pop completes an alignment for both players, the player who popped wins.
+    pub fn make_pop(&mut self, col: i32) {
+        let returned = self.rules.pop_returns_token(self, col);
+        self.toggle_column_keys(col);
+
+        let dims = self.dims();
+        let column = dims.column_mask(col);
+        let player = self.board.color_bitboard;
+        let opponent = self.board.bitboard ^ player;
+
+        let height = self.height(col);
+        let mut player = (player & !column) | ((player & column & !dims.bit(col, 0)) >> 1);
+        let opponent = (opponent & !column) | ((opponent & column) >> 1);
+        if returned {
+            player |= dims.bit(col, height - 1);
+            self.toggle_key(self.zobrist.side);
+        }
+        self.board.color_bitboard = opponent;
+        self.board.bitboard = player | opponent;
+        self.board.history.push(PlayedMove { mv: Move::pop(col), color: self.turn_color, returned });
         self.turn_color = self.turn_color.toggle();
+        self.toggle_column_keys(col);
+
+        self.outcome = self.rules.outcome(self);
+    }
+
+    /// Plays a move numbered like [Move::index], as returned by [Self::get_possible_moves].
+    pub fn make_move(&mut self, index: i32) {
+        let mv = Move::from_index(index, self.dims().width);
+        if mv.is_pop() {
+            self.make_pop(mv.col());
+        }
+        else {
+            self.make_push(mv.col());
+        }
+    }
+
+    /// Toggles the token of the color on the square in the keys, the one of the mirror included when it is tracked.
+    fn toggle_token_key(&mut self, col: i32, row: i32, color: Color) {
+        let dims = self.dims();
+        self.zobrist_key ^= self.zobrist.token(dims, col, row, color);
+        if self.track_mirror {
+            self.mirror_key ^= self.zobrist.token(dims, dims.mirror_col(col), row, color);
+        }
+    }
+
+    // side and phase keys are the same on the mirrored board
+    fn toggle_key(&mut self, key: u64) {
+        self.zobrist_key ^= key;
+        self.mirror_key ^= key;
+    }
+
+    /// Toggles every token of the column in the keys.
+    fn toggle_column_keys(&mut self, col: i32) {
+        let red = self.tokens(Color::Red);
+        for row in 0..self.height(col) {
+            let color = if red & self.dims().bit(col, row) != 0 { Color::Red } else { Color::Yellow };
+            self.toggle_token_key(col, row, color);
+        }
+    }
+
+    /// Checks that the column can be played in the current position.
+    pub fn validate_push(&self, col: i32) -> Result<(), MoveError> {
+        if !(0..self.dims().width).contains(&col) {
+            return Err(MoveError::OutOfRange(col));
+        }
+        if self.outcome.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if self.height(col) >= self.dims().height {
+            return Err(MoveError::ColumnFull(col));
+        }
+        if !self.rules.can_push(self, col) {
+            return Err(MoveError::InvalidPush(col));
+        }
+        Ok(())
+    }
+
+    /// Checks that the side to move can pop its bottom token from the column.
+    pub fn validate_pop(&self, col: i32) -> Result<(), MoveError> {
+        if !(0..self.dims().width).contains(&col) {
+            return Err(MoveError::OutOfRange(col));
+        }
+        if self.outcome.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if !self.rules.can_pop(self, col) {
+            return Err(MoveError::InvalidPop(col));
+        }
+        Ok(())
+    }
+
+    /// Validated version of [`Self::make_push()`] and [`Self::make_pop()`], the game is left untouched on error.
+    pub fn try_play(&mut self, mv: Move) -> Result<(), MoveError> {
+        if mv.is_pop() {
+            self.validate_pop(mv.col())?;
+            self.make_pop(mv.col());
+        }
+        else {
+            self.validate_push(mv.col())?;
+            self.make_push(mv.col());
+        }
+        Ok(())
     }
 
-    /// Debug function to start from a game history.
+    /// Plays a sequence of moves in [Move] notation, on error every move of the sequence is undone.
+    pub fn try_play_bulk(&mut self, history: &str) -> Result<(), MoveError> {
+        let moves = Move::parse_sequence(history)?;
+        for (played, &mv) in moves.iter().enumerate() {
+            if let Err(err) = self.try_play(mv) {
+                for _ in 0..played {
+                    self.unmake_move();
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Debug function to start from a game history in [Move] notation, panics on invalid histories.
     pub fn make_push_bulk(&mut self, history: &str) {
-        for char in history.chars().into_iter() {
-            let col: i32 = char.to_digit(10).unwrap() as i32;
-            self.make_push(col);
+        if let Err(err) = self.try_play_bulk(history) {
+            panic!("invalid history {}: {}", history, err);
+        }
+    }
+
+    /// Unmake the last move in history, push or pop.
+    pub fn unmake_move(&mut self) {
+        if self.board.history.last().unwrap().mv.is_pop() {
+            self.unmake_pop();
+        }
+        else {
+            self.unmake_push();
         }
     }
 
-    /// Unmake the last move in history.
+    /// Unmake the last move in history, which must be a push.
     pub fn unmake_push(&mut self) {
+        let PlayedMove { mv: last_move, color, .. } = self.board.history.pop().unwrap();
+        if self.board.bitboard == self.dims().full() && self.rules.fills_board_first() {
+            self.toggle_key(self.zobrist.phase);
+        }
         self.turn_color = self.turn_color.toggle();
-        let last_play = self.board.history.pop().unwrap();
-        self.zobrist_key ^= ZOBRIST_TABLE.token_square[Zobrist::get_index(last_play) as usize];
+        let dims = self.dims();
+        let col = last_move.col();
+        let bit = dims.above_top(self.board.bitboard, col) >> 1;
+        self.toggle_token_key(col, bit.trailing_zeros() as i32 - col * dims.stride(), color);
 
-        self.board.bitboard ^= last_play.0;
+        self.board.bitboard ^= bit;
         self.board.color_bitboard ^= self.board.bitboard;
-        
-        let col: u64 = last_play.0.into();
-        self.board.heights[(col.trailing_zeros() % 7) as usize] -= 1;
 
-        self.winner = None;
+        self.outcome = GameOutcome::Ongoing;
+    }
+
+    /// Puts back the token removed by the last move, a pop.
+    fn unmake_pop(&mut self) {
+        let PlayedMove { mv: last_move, returned, .. } = self.board.history.pop().unwrap();
+        let col = last_move.col();
+        self.toggle_column_keys(col);
+
+        let dims = self.dims();
+        let column = dims.column_mask(col);
+        let opponent = self.board.color_bitboard;
+        let mut player = self.board.bitboard ^ opponent;
+        if returned {
+            player ^= dims.bit(col, self.height(col) - 1);
+            self.toggle_key(self.zobrist.side);
+        }
+
+        let player = (player & !column) | ((player & column) << 1) | dims.bit(col, 0);
+        let opponent = (opponent & !column) | ((opponent & column) << 1);
+        self.board.color_bitboard = player;
+        self.board.bitboard = player | opponent;
+        self.turn_color = self.turn_color.toggle();
+        self.toggle_column_keys(col);
+
+        self.outcome = GameOutcome::Ongoing;
     }
 
-    /// Check if the board has a 4-alignment for the player who just played and returns his color if true.
+    /// Check if the board has an alignment of `connect` tokens and returns the color of the winner, the player who
+    /// just played unless a pop only completed an alignment of the opponent.
     pub fn check_win(&self) -> Option<Color> {
-        if let Some(last_flipped_bit) = self.board.history.last() {
-            let color_bitboard = self.board.color_bitboard ^ self.board.bitboard;
-            let a_clear: u42 = !File::A.mask();
-            let g_clear: u42 = !File::G.mask();
-
-            // vertical go down;
-            // println!("color board: {:042b}", color_bitboard);
-            let m = color_bitboard & (color_bitboard >> (WIDTH));
-            if (m & (m >> (2*(WIDTH)))) != EMPTY_BOARD {
-                // println!("win v");
-                return Some(last_flipped_bit.1);
-            }
-            
-            // horizontal, go left and right
-            let m1 = color_bitboard & (color_bitboard >> 1) & a_clear & g_clear; // To detect pairs like [_ _ X X], check if a piece has a RIGHT neighbor
-            let m2 = color_bitboard & (color_bitboard << 1) & a_clear & g_clear; // To detect pairs like [X X _ _], check if a piece has a LEFT neighbor
-            if (m1 & (m1 >> 2)) != EMPTY_BOARD || (m2 & (m2 << 2)) != EMPTY_BOARD {
-                // println!("win h: {:042b}", m);
-                return Some(last_flipped_bit.1);
+        let &PlayedMove { mv: last_move, color, .. } = self.board.history.last()?;
+        let player = self.board.color_bitboard ^ self.board.bitboard;
+
+        if self.has_alignment(player) {
+            return Some(color);
+        }
+        // the tokens of the opponent fall down too
+        if last_move.is_pop() && self.has_alignment(self.board.color_bitboard) {
+            return Some(color.toggle());
+        }
+        None
+    }
+
+    /// Returns the tokens that are part of an alignment of `connect` tokens.
+    pub fn aligned_squares(&self, tokens: Bitboard) -> Bitboard {
+        let dims = self.dims();
+        let mut aligned = 0;
+        for direction in DIRECTIONS {
+            let offset = dims.offset(direction);
+            let runs = Self::runs(tokens, offset, dims.connect);
+            for k in 0..dims.connect {
+                aligned |= shift_bits(runs, k * offset);
             }
-            
-            // Diagonal ↗ (up-right) - need to prevent wraparound on both shifts
-            // To detect pairs like 
-            //[_ _ _ X]
-            //[_ _ X _]
-            let m1 = color_bitboard & (color_bitboard >> (WIDTH + 1)) & a_clear & g_clear; 
-            // To detect pairs like 
-            // [_ X _ _]
-            // [X _ _ _]
-            let m2 = color_bitboard & (color_bitboard << (WIDTH + 1)) & a_clear & g_clear; 
-            if (m1 & (m1 >> 2 * (WIDTH + 1))) != EMPTY_BOARD || (m2 & (m2 << 2 * (WIDTH + 1))) != EMPTY_BOARD {
-                // println!("win h: {:042b}", m);
-                return Some(last_flipped_bit.1);
+        }
+        aligned
+    }
+
+    /// Whether the tokens contain an alignment of `connect` tokens.
+    fn has_alignment(&self, tokens: Bitboard) -> bool {
+        let dims = self.dims();
+        // checked after every move: on a u64 when the board fits in one, with the loop of Self::runs unrolled for the
+        // usual connect
+        match (dims.fits_u64(), dims.connect) {
+            (true, CONNECT) => Self::has_runs(tokens as u64, dims, CONNECT),
+            (true, connect) => Self::has_runs(tokens as u64, dims, connect),
+            (false, connect) => Self::has_runs(tokens, dims, connect)
+        }
+    }
+
+    #[inline(always)]
+    fn has_runs<B: Bits>(tokens: B, dims: Dimensions, length: i32) -> bool {
+        DIRECTIONS.iter().any(|&direction| Self::runs(tokens, dims.offset(direction), length) != B::ZERO)
+    }
+
+    /// Returns the first token of each run of `length` tokens `offset` bits apart, the offset being positive.
+    ///
+    /// Only the pairs of neighbours need to be on the board, a run leaving it goes through an empty sentinel.
+    #[inline(always)]
+    fn runs<B: Bits>(tokens: B, offset: i32, length: i32) -> B {
+        // doubling the length at each step: pairs, then pairs of pairs...
+        let mut runs = tokens;
+        let mut current = 1;
+        while current < length && runs != B::ZERO {
+            let step = current.min(length - current);
+            runs &= runs.shr_or_zero((step * offset) as u32);
+            current += step;
+        }
+        runs
+    }
+
+    /// Keeps the zobrist key of the mirrored position up to date from now on, rather than computing it on each call
+    /// of [Self::canonical_key]. Searches and cached perfts track it, other games don't pay for the key.
+    pub fn track_mirror(&mut self) {
+        if !self.track_mirror {
+            self.mirror_key = self.mirror_key();
+            self.track_mirror = true;
+        }
+    }
+
+    fn mirror_key(&self) -> u64 {
+        if self.track_mirror {
+            return self.mirror_key;
+        }
+        let dims = self.dims();
+        // the side and phase keys are shared with the key of the position
+        let mut mirror_key = self.zobrist_key;
+        for color in [Color::Red, Color::Yellow] {
+            let tokens = self.tokens(color);
+            for col in 0..dims.width {
+                for row in (0..dims.height).filter(|&row| tokens & dims.bit(col, row) != 0) {
+                    mirror_key ^= self.zobrist.token(dims, col, row, color) ^ self.zobrist.token(dims, dims.mirror_col(col), row, color);
+                }
             }
-            
-            // Diagonal ↖ (up-left) - need to prevent wraparound on both shifts
-            // Mask column A (and B for the second shift)
-            let m1 = color_bitboard & (color_bitboard >> (WIDTH - 1)) & a_clear & g_clear;
-            let m2 = color_bitboard & (color_bitboard << (WIDTH - 1)) & a_clear & g_clear;
-            if (m1 & (m1 >> 2 * (WIDTH - 1))) != EMPTY_BOARD || (m2 & (m2 << 2 * (WIDTH - 1))) != EMPTY_BOARD {
-                // println!("win h: {:042b}", m);
-                return Some(last_flipped_bit.1);
+        }
+        mirror_key
+    }
+
+    /// Returns the key shared by the position and its mirror, the smallest of their zobrist keys.
+    pub fn canonical_key(&self) -> u64 {
+        self.zobrist_key.min(self.mirror_key())
+    }
+
+    /// Whether [Self::canonical_key] is the key of the mirrored position,
+    /// columns stored along with the canonical key must then be mirrored.
+    pub fn is_mirrored(&self) -> bool {
+        self.mirror_key() < self.zobrist_key
+    }
+
+    /// Returns the game flipped left to right, replaying the mirrored history.
+    pub fn mirrored(&self) -> Game {
+        let width = self.dims().width;
+        // the setup moves are mirrored too, so they are replayed rather than played by the rules
+        let mut game = Game { rules: self.rules.clone(), setup_len: self.setup_len, ..Game::with_dimensions(self.dims()) };
+        for played in self.board.history.iter() {
+            game.make_move(played.mv.mirrored(width).index(width));
+        }
+        game
+    }
+
+    /// Returns a key of the position, stable across versions unlike [Game::zobrist_key].
+    ///
+    /// Each column uses `height + 1` bits from the first one: a bit set for each red token from the bottom, then a bit
+    /// set right above the top token. The next bit is set when the side to move doesn't follow from the token count,
+    /// which only happens after a pop putting the token back on top of its column, and the one after it once the
+    /// board has been filled, see [Self::board_filled].
+    ///
+    /// The key is collision-free when `width * (height + 1)` is less than 63, on larger boards the bits past the 64th
+    /// are folded onto the first ones.
+    pub fn position_key(&self) -> u64 {
+        let dims = self.dims();
+        let red_to_move = self.board.bitboard.count_ones().is_multiple_of(2);
+        let side = Bitboard::from(red_to_move != (self.turn_color == Color::Red)) << (dims.width * dims.stride());
+        let phase = Bitboard::from(self.board_filled()) << (dims.width * dims.stride() + 1);
+        // the carry of the addition sets the bit above each column
+        let key = self.tokens(Color::Red) + self.board.bitboard + dims.bottom_row() + side + phase;
+        key as u64 ^ (key >> 64) as u64
+    }
+
+    /// Whether the board has been filled in a game whose rules allow other moves afterwards,
+    /// see [Rules::fills_board_first].
+    pub fn board_filled(&self) -> bool {
+        self.rules.fills_board_first() && self.board.history.len() >= self.dims().squares() as usize
+    }
+
+    /// Returns the tokens of the given color.
+    pub fn tokens(&self, color: Color) -> Bitboard {
+        if color == self.turn_color {
+            self.board.color_bitboard
+        }
+        else {
+            self.board.color_bitboard ^ self.board.bitboard
+        }
+    }
+
+    /// Returns the squares where the next token of each non-full column would land.
+    pub fn playable_squares(&self) -> Bitboard {
+        (self.board.bitboard + self.dims().bottom_row()) & self.dims().full()
+    }
+
+    /// Returns the empty squares (playable or not) that would complete an alignment for the given color.
+    pub fn winning_squares(&self, color: Color) -> Bitboard {
+        let dims = self.dims();
+        let tokens = self.tokens(color);
+        let mut result = 0;
+
+        for direction in DIRECTIONS {
+            let offset = dims.offset(direction);
+            // the empty square can be at any position of the alignment
+            for k in 0..dims.connect {
+                let mut aligned = dims.full();
+                for j in (-k..dims.connect - k).filter(|&j| j != 0) {
+                    aligned &= shift_bits(tokens, -j * offset);
+                }
+                result |= aligned;
             }
         }
 
-        None
+        result & dims.full() & !self.board.bitboard
+    }
+
+    /// Returns the columns where the side to move wins immediately.
+    pub fn winning_moves(&self) -> Vec<i32> {
+        if self.outcome.is_over() {
+            return Vec::new();
+        }
+
+        let winning = self.winning_squares(self.turn_color) & self.playable_squares();
+        self.columns(winning)
+    }
+
+    /// Returns the moves that don't lose immediately: the winning moves if there are any, otherwise the block
+    /// of the only opponent threat, never playing right under an opponent winning square.
+    /// The result is empty when every move loses.
+    pub fn non_losing_moves(&self) -> Vec<i32> {
+        let winning_moves = self.winning_moves();
+        if !winning_moves.is_empty() || self.outcome.is_over() {
+            return winning_moves;
+        }
+
+        let playable = self.playable_squares();
+        let opponent_wins = self.winning_squares(self.turn_color.toggle());
+        let forced = playable & opponent_wins;
+
+        let candidates = match forced.count_ones() {
+            0 => playable,
+            1 => forced,
+            _ => return Vec::new()
+        };
+
+        self.columns(candidates & !(opponent_wins >> 1))
+    }
+
+    /// Returns the columns of the squares in `bitboard`, from the last column to the first like [Self::get_possible_moves].
+    fn columns(&self, bitboard: Bitboard) -> Vec<i32> {
+        let dims = self.dims();
+        let mut result = Vec::with_capacity(dims.width as usize);
+        // the move generation goes through here, shifting a u64 is cheaper when the board fits in one
+        if dims.fits_u64() {
+            let (bitboard, column) = (bitboard as u64, dims.column_mask(0) as u64);
+            result.extend((0..dims.width).rev().filter(|&col| bitboard >> (col * dims.stride()) & column != 0));
+        }
+        else {
+            result.extend((0..dims.width).rev().filter(|&col| bitboard & dims.column_mask(col) != 0));
+        }
+        result
     }
 
     /// Main function to start the game.
     pub fn run(&mut self) {
         let depth = input_difficulty();
-        let mut search = Search::new(depth);
+        let mut search = Search::new(depth).with_time_limit(THINK_TIME);
         
-        let mut move_history = String::new();
+        let mut record = GameRecord::new("Human", "Negamax");
+        record.yellow_depth = Some(depth);
+        record.date = Some(GameRecord::today());
         self.board.display_board();
         loop {
+            // keep searching while the player thinks, so the transposition table is warm for the reply
+            let ponder = search.ponder(self);
+
             println!("choose a column to play (1-7): ");
-            let col = play() - 1;
-            if !self.get_possible_moves().contains(&col) {
-                continue;
+            let mut mv = play();
+            while let Err(err) = self.try_play(mv) {
+                println!("{}, choose another column (1-7): ", err);
+                mv = play();
             }
+            search = ponder.stop();
             
-            self.make_push(col);
-            move_history += &col.to_string();
+            record.push(mv, None, None);
             self.board.display_board();
             
-            if self.winner.is_some() {
-                println!("you won !");
+            match self.outcome {
+                GameOutcome::Win(_) => println!("you won !"),
+                GameOutcome::Draw => println!("it's a draw !"),
+                GameOutcome::Ongoing => ()
+            }
+            if self.outcome.is_over() {
                 break;
             }
 
             println!("AI is thinking...");
-            if let Some(best_move) = search.think(self) {
-                self.make_push(best_move);
-                move_history += &best_move.to_string();
+            let result = search.think(self);
+            if let Some(best_move) = result.best_move {
+                self.make_move(best_move.index(self.dims().width));
+                record.push(best_move, Some(result.score), None);
                 self.board.display_board();
+
+                let pv: Vec<String> = result.pv.iter().map(Move::to_string).collect();
+                println!("AI played {}, score: {}, depth: {}, pv: {}, nodes: {} in {:?}", best_move, result.score, result.depth, pv.join(" "), result.nodes, result.elapsed);
+                if let Some(value) = result.value {
+                    println!("AI sees a {}", value);
+                }
             }
 
-            if self.winner.is_some() {
-                println!("AI won!");
+            match self.outcome {
+                GameOutcome::Win(_) => println!("AI won!"),
+                GameOutcome::Draw => println!("it's a draw !"),
+                GameOutcome::Ongoing => ()
+            }
+            if self.outcome.is_over() {
                 break;
             }
         }
 
-        println!("{}", move_history);
-
+        record.result = self.outcome;
+        println!("{}", record);
     }
 }
 
+/// Counts the move paths from the position of `game`, to validate the move generation.
 #[derive(Default)]
 pub struct Perft {
     pub game: Game,
-    tt: HashMap<(u64, i32), u64>
+    tt: HashMap<(u64, i32), u64>, // (canonical_key, depth), node count
+    shared_tt: Option<Arc<PerftTable>> // used by the threads of [Perft::run_parallel]
 }
 
 impl Perft {
     pub fn new() -> Self {
         Self { ..Default::default() }
     }
+
+    /// Shares a table of `size_mb` megabytes between the threads of [Self::run_parallel].
+    pub fn with_shared_table(mut self, size_mb: usize) -> Self {
+        self.shared_tt = Some(Arc::new(PerftTable::new(size_mb)));
+        self
+    }
     
-    pub fn run(&mut self, depth: i32) -> u64 {        
+    /// Counts the move paths of `depth` moves, a negative depth counting as 0.
+    pub fn run(&mut self, depth: i32) -> u64 {
+        if depth <= 0 {
+            return 1;
+        }
+
         let possible_moves = self.game.get_possible_moves();
         
         if depth == 1 {
@@ -358,74 +1049,315 @@ impl Perft {
         
         let mut nodes: u64 = 0;
         for possible_move in possible_moves {
-            self.game.make_push(possible_move);
+            self.game.make_move(possible_move);
             nodes += self.run(depth - 1);
-            self.game.unmake_push();
+            self.game.unmake_move();
         }
 
         nodes
     }
 
+    /// Same as [Self::run], with the counts of the positions already met, their mirror included, kept between calls.
     pub fn run_tt(&mut self, depth: i32) -> u64 {
-        if let Some(&cached) = self.tt.get(&(self.game.zobrist_key, depth)) {
+        if depth <= 1 {
+            return self.run(depth);
+        }
+        self.game.track_mirror();
+        if let Some(&cached) = self.tt.get(&(self.game.canonical_key(), depth)) {
             return cached;
         }
         
-        let possible_moves = self.game.get_possible_moves();
-        
-        if depth == 1 {
-            let count = possible_moves.len() as u64;
-            self.tt.insert((self.game.zobrist_key, depth), count);
-            return count;
-        } 
-        
         let mut nodes: u64 = 0;
-        for possible_move in possible_moves {
-            self.game.make_push(possible_move);
-            nodes += self.run(depth - 1);
-            self.game.unmake_push();
+        for possible_move in self.game.get_possible_moves() {
+            self.game.make_move(possible_move);
+            nodes += self.run_tt(depth - 1);
+            self.game.unmake_move();
+        }
+
+        self.tt.insert((self.game.canonical_key(), depth), nodes);
+        nodes
+    }
+
+    /// Returns the positions reached at each depth from 0 to `depth`, by outcome, nothing for a negative depth.
+    pub fn stats(&mut self, depth: i32) -> Vec<PerftStats> {
+        if depth < 0 {
+            return Vec::new();
+        }
+
+        let mut stats = vec![PerftStats::default(); depth as usize + 1];
+        self.collect_stats(0, &mut stats);
+        stats
+    }
+
+    fn collect_stats(&mut self, ply: usize, stats: &mut [PerftStats]) {
+        stats[ply].add(self.game.outcome);
+        if ply + 1 == stats.len() {
+            return;
+        }
+
+        for possible_move in self.game.get_possible_moves() {
+            self.game.make_move(possible_move);
+            self.collect_stats(ply + 1, stats);
+            self.game.unmake_move();
+        }
+    }
+
+    /// Returns the number of distinct positions at each ply from 0 to `depth`, transpositions being counted once.
+    /// With `mirrors`, a position and its mirror are counted once too, see [Game::canonical_key].
+    pub fn unique_positions(&mut self, depth: i32, mirrors: bool) -> Vec<u64> {
+        self.visit_unique_positions(depth, mirrors, |_, _| ())
+    }
+
+    /// Calls `visit` with the ply of each distinct position, in depth-first order, and returns their count per ply.
+    /// Nothing is visited for a negative depth.
+    pub fn visit_unique_positions(&mut self, depth: i32, mirrors: bool, mut visit: impl FnMut(usize, &Game)) -> Vec<u64> {
+        if depth < 0 {
+            return Vec::new();
+        }
+
+        if mirrors {
+            self.game.track_mirror();
+        }
+        let mut seen = vec![HashSet::new(); depth as usize + 1];
+        self.collect_unique_positions(0, mirrors, &mut seen, &mut visit);
+        seen.iter().map(|keys| keys.len() as u64).collect()
+    }
+
+    /// Writes a line per distinct position with its ply and [Game::to_position], see [Self::visit_unique_positions].
+    pub fn write_unique_positions(&mut self, depth: i32, mirrors: bool, writer: &mut impl Write) -> io::Result<Vec<u64>> {
+        let mut result = Ok(());
+        let counts = self.visit_unique_positions(depth, mirrors, |ply, game| {
+            if result.is_ok() {
+                result = writeln!(writer, "{} {}", ply, game.to_position());
+            }
+        });
+        result.map(|_| counts)
+    }
+
+    fn collect_unique_positions(&mut self, ply: usize, mirrors: bool, seen: &mut [HashSet<u64>], visit: &mut impl FnMut(usize, &Game)) {
+        let key = if mirrors { self.game.canonical_key() } else { self.game.zobrist_key };
+        // the positions below a transposition have already been visited
+        if !seen[ply].insert(key) {
+            return;
+        }
+        visit(ply, &self.game);
+        if ply + 1 == seen.len() {
+            return;
+        }
+
+        for possible_move in self.game.get_possible_moves() {
+            self.game.make_move(possible_move);
+            self.collect_unique_positions(ply + 1, mirrors, seen, visit);
+            self.game.unmake_move();
+        }
+    }
+
+    /// Returns the node count below each root move, in the order of [Game::get_possible_moves].
+    /// There are no root moves to count below depth 1.
+    pub fn divide(&mut self, depth: i32) -> Vec<(Move, u64)> {
+        if depth <= 0 {
+            return Vec::new();
+        }
+
+        let width = self.game.dims().width;
+        let mut counts = Vec::new();
+        for possible_move in self.game.get_possible_moves() {
+            self.game.make_move(possible_move);
+            counts.push((Move::from_index(possible_move, width), self.run_tt(depth - 1)));
+            self.game.unmake_move();
+        }
+        counts
+    }
+
+    /// Same as [Self::run], with the subtrees two plies below the position counted by the threads of rayon.
+    pub fn run_parallel(&mut self, depth: i32) -> u64 {
+        if depth < 3 {
+            return self.run(depth);
+        }
+
+        let mut subtrees = Vec::new();
+        for first in self.game.get_possible_moves() {
+            self.game.make_move(first);
+            for second in self.game.get_possible_moves() {
+                self.game.make_move(second);
+                subtrees.push(self.game.clone());
+                self.game.unmake_move();
+            }
+            self.game.unmake_move();
+        }
+
+        subtrees.into_par_iter().map(|game| {
+            let mut perft = Perft { game, ..Default::default() };
+            match &self.shared_tt {
+                Some(table) => perft.run_shared(depth - 2, table),
+                None => perft.run(depth - 2)
+            }
+        }).sum()
+    }
+
+    fn run_shared(&mut self, depth: i32, table: &PerftTable) -> u64 {
+        if depth <= 1 {
+            return self.run(depth);
+        }
+        self.game.track_mirror();
+        let key = self.game.canonical_key();
+        if let Some(cached) = table.probe(key, depth) {
+            return cached;
+        }
+
+        let mut nodes: u64 = 0;
+        for possible_move in self.game.get_possible_moves() {
+            self.game.make_move(possible_move);
+            nodes += self.run_shared(depth - 1, table);
+            self.game.unmake_move();
         }
 
-        self.tt.insert((self.game.zobrist_key, depth), nodes);
+        table.store(key, depth, nodes);
         nodes
     }
 
     pub fn reset(&mut self) {
         self.tt.clear();
-        self.game = Default::default();
+        if let Some(table) = &self.shared_tt {
+            table.clear();
+        }
+        self.game.reset();
     }
 }
 
+/// Distinct positions of the standard 7x6 game at each ply, indexed by ply.
+pub const UNIQUE_POSITION_COUNTS: [u64; 11] = [
+    1, 7, 49, 238, 1120, 4263, 16422, 54859, 184275, 558186, 1662623
+];
+
+/// Positions reached at a depth of [Perft::stats], their sum being the perft of the depth.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PerftStats {
+    pub ongoing: u64,
+    pub red_wins: u64,
+    pub yellow_wins: u64,
+    pub draws: u64
+}
+
+impl PerftStats {
+    fn add(&mut self, outcome: GameOutcome) {
+        match outcome {
+            GameOutcome::Ongoing => self.ongoing += 1,
+            GameOutcome::Win(Color::Red) => self.red_wins += 1,
+            GameOutcome::Win(Color::Yellow) => self.yellow_wins += 1,
+            GameOutcome::Draw => self.draws += 1
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.ongoing + self.red_wins + self.yellow_wins + self.draws
+    }
+}
+
+/// Node counts shared by the threads of [Perft::run_parallel] without locks, each position replacing the previous one
+/// of its slot.
+pub struct PerftTable {
+    slots: Box<[Slot]>
+}
+
+impl PerftTable {
+    pub fn new(size_mb: usize) -> Self {
+        Self { slots: allocate(size_mb) }
+    }
 
+    // the depth is part of the key, so that the depths of a position don't share a slot
+    fn slot(&self, key: u64, depth: i32) -> (u64, &Slot) {
+        let key = key ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        (key, &self.slots[key as usize & (self.slots.len() - 1)])
+    }
+
+    /// The count is stored with the depth, so that an empty slot is never a hit.
+    pub fn probe(&self, key: u64, depth: i32) -> Option<u64> {
+        let (key, slot) = self.slot(key, depth);
+        let (slot_key, data) = slot.load();
+        (data != 0 && slot_key == key && (data & 0xff) as i32 == depth).then_some(data >> 8)
+    }
+
+    /// The count shares its word with the depth, counts of `2^56` nodes or more and depths past 255 aren't stored.
+    pub fn store(&self, key: u64, depth: i32, nodes: u64) {
+        if nodes >> 56 != 0 || depth > 0xff {
+            return;
+        }
+        let (key, slot) = self.slot(key, depth);
+        slot.store(key, nodes << 8 | depth as u64);
+    }
+
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.store(0, 0);
+        }
+    }
+}
+
+/// Perft of the standard 7x6 game from the empty board, indexed by depth.
+pub const PERFT_COUNTS: [u64; 12] = [
+    1, 7, 49, 343, 2401, 16807, 117649, 823536, 5673234, 39394572, 268031646, 1844590828
+];
+
+
+// the table has a key per color for each of the squares a board can have at most
 struct Zobrist {
-    token_square: [u64; 84] // 42 * 2
+    token_square: [u64; 2 * MAX_SQUARES as usize],
+    side: u64, // toggled by the pops putting the token back on top of its column
+    phase: u64 // toggled when the board is filled, see [Rules::fills_board_first]
 }
 
 impl Default for Zobrist {
     fn default() -> Self {
         Self {
-            token_square: [0u64; 84],
+            token_square: [0u64; 2 * MAX_SQUARES as usize],
+            side: 0,
+            phase: 0
         }
     }
 }
 
-static ZOBRIST_TABLE: Lazy<Zobrist> = Lazy::new(|| {
-    let mut z = Zobrist::default();
-    for i in 0..84 {
-        z.token_square[i] = random();
-    }
-    z
-});
+static ZOBRIST_TABLE: OnceCell<Zobrist> = OnceCell::new();
+
+/// Seeds the Zobrist keys, must be called before the first [Game] is created.
+/// Returns `false` if the keys were already generated, in which case they are left unchanged.
+pub fn set_zobrist_seed(seed: u64) -> bool {
+    ZOBRIST_TABLE.set(Zobrist::new(seed)).is_ok()
+}
 
 impl Zobrist {
-    fn get_index(play: (u42, Color)) -> u64 {
-        let raw_u64: u64 = play.0.into();
-        let offset = match play.1.to_int() {
-            1 => 0,
-            -1 => 1,
-            _ => unreachable!()
+    /// Fills the table with splitmix64, so the keys only depend on the seed and not on the `rand` version.
+    fn new(seed: u64) -> Self {
+        let mut z = Zobrist::default();
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_add(0x9E3779B97F4A7C15);
+            let mut x = state;
+            x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+            x ^ (x >> 31)
         };
+        for key in z.token_square.iter_mut() {
+            *key = next();
+        }
+        // drawn last so that the keys of the tokens don't depend on them
+        z.side = next();
+        z.phase = next();
+        z
+    }
 
-        offset as u64 * 42 + raw_u64.trailing_zeros() as u64
+    fn table() -> &'static Zobrist {
+        ZOBRIST_TABLE.get_or_init(|| Zobrist::new(ZOBRIST_SEED))
     }
-}
\ No newline at end of file
+
+    /// Squares are numbered row by row and yellow keys follow the red ones, which keeps the keys of the standard board
+    /// whatever its size and the layout of the bitboards.
+    fn token(&self, dims: Dimensions, col: i32, row: i32, color: Color) -> u64 {
+        let offset = match color {
+            Color::Red => 0,
+            Color::Yellow => dims.squares()
+        };
+
+        self.token_square[(offset + row * dims.width + col) as usize]
+    }
+}
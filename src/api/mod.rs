@@ -1,2 +1,6 @@
 pub mod engine;
-pub mod search;
\ No newline at end of file
+pub mod position;
+pub mod record;
+pub mod rules;
+pub mod search;
+pub mod tt;
\ No newline at end of file
@@ -0,0 +1,230 @@
+use core::fmt;
+use std::{collections::HashSet, str::FromStr, sync::Arc};
+
+use crate::api::{engine::{Color, Dimensions, Game, CONNECT}, rules::{rules_from_name, Rules, Standard}};
+
+/// Reasons for [Game::from_position] to reject a position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PositionError {
+    InvalidFormat,
+    InvalidRow(i32), // counted from the top
+    InvalidSize, // see [Dimensions::new], or not the size imposed by the variant
+    InvalidConnect, // see [Dimensions::with_connect], or not the count imposed by the variant
+    UnknownVariant, // see [rules_from_name]
+    FloatingToken(i32), // column with a token above an empty square
+    InvalidTokenCount,
+    WrongSideToMove,
+    Unreachable // no move order leads to the position without the game ending earlier
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::InvalidFormat => write!(f, "expected rows separated by '/' followed by the side to move, the connect count, the variant and the phase"),
+            PositionError::InvalidRow(row) => write!(f, "row {} is invalid", row + 1),
+            PositionError::InvalidSize => write!(f, "the board size is not supported"),
+            PositionError::InvalidConnect => write!(f, "the number of tokens to align is not supported"),
+            PositionError::UnknownVariant => write!(f, "the variant is unknown"),
+            PositionError::FloatingToken(col) => write!(f, "column {} has a token above an empty square", col + 1),
+            PositionError::InvalidTokenCount => write!(f, "red must have as many tokens as yellow, or one more"),
+            PositionError::WrongSideToMove => write!(f, "the side to move doesn't match the token count"),
+            PositionError::Unreachable => write!(f, "the position can't be reached in a game")
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Position notation, similar to chess' FEN: rows from top to bottom separated by `/`, `R` and `Y` for tokens
+/// and digits for runs of empty squares, followed by the side to move. The number of tokens to align follows when it
+/// isn't [CONNECT], then the [Rules::name] of the variant when it isn't [Standard], and `filled` once the board has
+/// been filled in a variant such as [Pop Ten](crate::api::rules::PopTen), see [Game::board_filled].
+///
+/// The starting position is `7/7/7/7/7/7 R`, the size of the board is given by the number of rows and their length.
+/// Only the positions reached by pushing tokens are loaded, the ones that need pops are rejected as unreachable.
+impl Game {
+    pub fn from_position(position: &str) -> Result<Game, PositionError> {
+        let mut fields = position.split_whitespace();
+        let (Some(rows), Some(side)) = (fields.next(), fields.next()) else {
+            return Err(PositionError::InvalidFormat);
+        };
+        let turn_color = match side {
+            "R" => Color::Red,
+            "Y" => Color::Yellow,
+            _ => return Err(PositionError::InvalidFormat)
+        };
+
+        // the fields are optional, a number being the connect count and anything else the variant
+        let mut field = fields.next();
+        let connect = match field.map(str::parse::<i32>) {
+            Some(Ok(connect)) => {
+                field = fields.next();
+                connect
+            }
+            _ => CONNECT
+        };
+        let rules: Arc<dyn Rules> = match field {
+            Some(name) => rules_from_name(name).ok_or(PositionError::UnknownVariant)?,
+            None => Arc::new(Standard)
+        };
+        let board_filled = match fields.next() {
+            Some("filled") if rules.fills_board_first() => true,
+            None => false,
+            Some(_) => return Err(PositionError::InvalidFormat)
+        };
+        if fields.next().is_some() {
+            return Err(PositionError::InvalidFormat);
+        }
+
+        let rows: Vec<&str> = rows.split('/').collect();
+        let width = rows[0].chars().map(|c| c.to_digit(10).unwrap_or(1) as i32).sum();
+        let dims = Dimensions::new(width, rows.len() as i32).ok_or(PositionError::InvalidSize)?;
+        let dims = dims.with_connect(connect).ok_or(PositionError::InvalidConnect)?;
+        let imposed = rules.dimensions(dims);
+        if (imposed.width, imposed.height) != (dims.width, dims.height) {
+            return Err(PositionError::InvalidSize);
+        }
+        if imposed.connect != dims.connect {
+            return Err(PositionError::InvalidConnect);
+        }
+        let (width, height) = (dims.width as usize, dims.height as usize);
+
+        // grid[col][row], row 0 being the bottom
+        let mut grid = vec![vec![None; height]; width];
+        for (index, row_str) in rows.iter().enumerate() {
+            let row = height - 1 - index;
+            let mut col = 0usize;
+            for char in row_str.chars() {
+                match char {
+                    'R' | 'Y' if col < width => {
+                        grid[col][row] = Some(if char == 'R' { Color::Red } else { Color::Yellow });
+                        col += 1;
+                    }
+                    '1'..='9' => col += char.to_digit(10).unwrap() as usize,
+                    _ => return Err(PositionError::InvalidRow(index as i32))
+                }
+            }
+            if col != width {
+                return Err(PositionError::InvalidRow(index as i32));
+            }
+        }
+
+        let mut heights = vec![0usize; width];
+        for (col, column) in grid.iter().enumerate() {
+            heights[col] = column.iter().take_while(|token| token.is_some()).count();
+            if column[heights[col]..].iter().any(|token| token.is_some()) {
+                return Err(PositionError::FloatingToken(col as i32));
+            }
+        }
+
+        let red = grid.iter().flatten().filter(|&&token| token == Some(Color::Red)).count();
+        let yellow = grid.iter().flatten().filter(|&&token| token == Some(Color::Yellow)).count();
+        let expected_turn = match red as i32 - yellow as i32 {
+            0 => Color::Red,
+            1 => Color::Yellow,
+            _ => return Err(PositionError::InvalidTokenCount)
+        };
+        if turn_color != expected_turn {
+            return Err(PositionError::WrongSideToMove);
+        }
+
+        // the tokens played by the setup of the variant must be in the grid
+        let mut game = Game::with_shared_rules(rules, dims);
+        let mut filled: Vec<usize> = (0..width).map(|col| game.height(col as i32) as usize).collect();
+        let red = game.tokens(Color::Red);
+        for (col, column) in grid.iter().enumerate() {
+            let matches = (0..filled[col]).all(|row| {
+                let color = if red & dims.bit(col as i32, row as i32) != 0 { Color::Red } else { Color::Yellow };
+                column[row] == Some(color)
+            });
+            if !matches {
+                return Err(PositionError::Unreachable);
+            }
+        }
+
+        // the phase only depends on the number of moves, and every move is a push
+        let mut dead_ends = HashSet::new();
+        if Self::replay_position(&mut game, &grid, &heights, &mut filled, &mut dead_ends) && game.board_filled() == board_filled {
+            Ok(game)
+        }
+        else {
+            Err(PositionError::Unreachable)
+        }
+    }
+
+    /// Looks for a move order filling every column up to `heights`, the game must not end before the last token.
+    /// Positions from which the grid can't be completed are remembered in `dead_ends`.
+    fn replay_position(game: &mut Game, grid: &[Vec<Option<Color>>], heights: &[usize], filled: &mut [usize], dead_ends: &mut HashSet<u64>) -> bool {
+        if filled == heights {
+            return true;
+        }
+        if game.outcome.is_over() || dead_ends.contains(&game.zobrist_key) {
+            return false;
+        }
+
+        for col in 0..grid.len() {
+            if filled[col] < heights[col] && grid[col][filled[col]] == Some(game.turn_color) {
+                game.make_push(col as i32);
+                filled[col] += 1;
+                if Self::replay_position(game, grid, heights, filled, dead_ends) {
+                    return true;
+                }
+                filled[col] -= 1;
+                game.unmake_push();
+            }
+        }
+
+        dead_ends.insert(game.zobrist_key);
+        false
+    }
+
+    pub fn to_position(&self) -> String {
+        let dims = self.dims();
+        let red = self.tokens(Color::Red);
+        let yellow = self.tokens(Color::Yellow);
+
+        let mut rows = Vec::new();
+        for row in (0..dims.height).rev() {
+            let mut row_str = String::new();
+            let mut empty = 0;
+            for col in 0..dims.width {
+                let bit = dims.bit(col, row);
+                let token = if red & bit != 0 { 'R' } else if yellow & bit != 0 { 'Y' } else { ' ' };
+                if token == ' ' {
+                    empty += 1;
+                    continue;
+                }
+
+                if empty > 0 {
+                    row_str += &empty.to_string();
+                    empty = 0;
+                }
+                row_str.push(token);
+            }
+            if empty > 0 {
+                row_str += &empty.to_string();
+            }
+            rows.push(row_str);
+        }
+
+        let mut position = format!("{} {}", rows.join("/"), self.turn_color);
+        if dims.connect != CONNECT {
+            position += &format!(" {}", dims.connect);
+        }
+        if self.rules.name() != Standard.name() {
+            position += &format!(" {}", self.rules.name());
+        }
+        if self.board_filled() {
+            position += " filled";
+        }
+        position
+    }
+}
+
+impl FromStr for Game {
+    type Err = PositionError;
+
+    fn from_str(position: &str) -> Result<Self, Self::Err> {
+        Game::from_position(position)
+    }
+}
@@ -0,0 +1,299 @@
+use core::fmt;
+use std::{fs, io, path::Path, str::FromStr, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::api::{engine::{Color, Dimensions, Game, GameOutcome, Move, MoveError, CONNECT, HEIGHT, WIDTH}, rules::{rules_from_name, Rules, Standard}};
+
+/// A move of a [GameRecord], with the engine score and a free comment if any.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RecordedMove {
+    pub mv: Move,
+    pub score: Option<i32>,
+    pub comment: Option<String>
+}
+
+impl RecordedMove {
+    pub fn new(mv: Move) -> Self {
+        Self { mv, score: None, comment: None }
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(io::Error),
+    InvalidTag(String),
+    InvalidMove(String),
+    InvalidScore(String),
+    UnclosedComment,
+    IllegalMove(usize, MoveError) // index of the move in the record
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Io(err) => write!(f, "{}", err),
+            RecordError::InvalidTag(line) => write!(f, "invalid tag: {}", line),
+            RecordError::InvalidMove(token) => write!(f, "invalid move: {}", token),
+            RecordError::InvalidScore(comment) => write!(f, "invalid score in comment: {}", comment),
+            RecordError::UnclosedComment => write!(f, "unclosed comment"),
+            RecordError::IllegalMove(index, err) => write!(f, "move {}: {}", index + 1, err)
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<io::Error> for RecordError {
+    fn from(err: io::Error) -> Self {
+        RecordError::Io(err)
+    }
+}
+
+/// PGN-like record of a game: tags between brackets, then the numbered move list.
+///
+/// ```text
+/// [Red "Human"]
+/// [Yellow "Negamax"]
+/// [YellowDepth "8"]
+/// [Date "2025.01.31"]
+/// [Result "0-1"]
+///
+/// 1. 4 4 {[%eval 12] center reply} 2. 5 ... 0-1
+/// ```
+///
+/// Moves use the 1-based [Move] notation, scores are stored in comments as `[%eval score]`.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct GameRecord {
+    pub red: String,
+    pub yellow: String,
+    pub red_depth: Option<i32>, // search depth when the player is an engine
+    pub yellow_depth: Option<i32>,
+    pub date: Option<String>, // YYYY.MM.DD
+    pub dims: Dimensions, // size and connect tags are only written when they differ from the standard game
+    pub variant: Option<String>, // name of the rules when not playing the standard game, see [rules_from_name]
+    pub result: GameOutcome,
+    pub moves: Vec<RecordedMove>
+}
+
+impl GameRecord {
+    pub fn new(red: &str, yellow: &str) -> Self {
+        Self {
+            red: red.to_string(),
+            yellow: yellow.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Builds a record from the history of a game, without scores nor comments.
+    pub fn from_game(game: &Game, red: &str, yellow: &str) -> Self {
+        let mut record = Self::new(red, yellow);
+        // the moves played by the rules are replayed by their setup
+        record.moves = game.board.history.iter().skip(game.setup_len)
+            .map(|played| RecordedMove::new(played.mv))
+            .collect();
+        record.dims = game.dims();
+        record.variant = Some(game.rules.name()).filter(|&name| name != Standard.name()).map(str::to_string);
+        record.result = game.outcome;
+        record
+    }
+
+    /// Today's date in the record format, in UTC.
+    pub fn today() -> String {
+        let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() / 86400) as i64;
+
+        // days to civil date, from Howard Hinnant's algorithm
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as i64;
+
+        format!("{:04}.{:02}.{:02}", year, month, day)
+    }
+
+    pub fn push(&mut self, mv: Move, score: Option<i32>, comment: Option<&str>) {
+        self.moves.push(RecordedMove {
+            mv,
+            score,
+            comment: comment.map(str::to_string)
+        });
+    }
+
+    /// Plays the first `moves` moves of the record from the starting position.
+    pub fn replay_to(&self, moves: usize) -> Result<Game, RecordError> {
+        let rules = rules_from_name(self.variant.as_deref().unwrap_or(Standard.name())).ok_or(RecordError::InvalidTag("Variant".to_string()))?;
+        let mut game = Game::with_shared_rules(rules, self.dims);
+        for (index, recorded) in self.moves.iter().take(moves).enumerate() {
+            game.try_play(recorded.mv).map_err(|err| RecordError::IllegalMove(index, err))?;
+        }
+        Ok(game)
+    }
+
+    /// Plays every move of the record from the starting position.
+    pub fn replay(&self) -> Result<Game, RecordError> {
+        self.replay_to(self.moves.len())
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, RecordError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), RecordError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    fn result_str(&self) -> &'static str {
+        match self.result {
+            GameOutcome::Win(Color::Red) => "1-0",
+            GameOutcome::Win(Color::Yellow) => "0-1",
+            GameOutcome::Draw => "1/2-1/2",
+            GameOutcome::Ongoing => "*"
+        }
+    }
+
+    fn parse_result(value: &str) -> Option<GameOutcome> {
+        match value {
+            "1-0" => Some(GameOutcome::Win(Color::Red)),
+            "0-1" => Some(GameOutcome::Win(Color::Yellow)),
+            "1/2-1/2" => Some(GameOutcome::Draw),
+            "*" => Some(GameOutcome::Ongoing),
+            _ => None
+        }
+    }
+
+    fn parse_tag(&mut self, line: &str) -> Result<(), RecordError> {
+        let invalid = || RecordError::InvalidTag(line.to_string());
+        let inner = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')).ok_or_else(invalid)?;
+        let (name, value) = inner.split_once(' ').ok_or_else(invalid)?;
+        let value = value.trim().strip_prefix('"').and_then(|v| v.strip_suffix('"')).ok_or_else(invalid)?;
+
+        match name {
+            "Red" => self.red = value.to_string(),
+            "Yellow" => self.yellow = value.to_string(),
+            "RedDepth" => self.red_depth = Some(value.parse().map_err(|_| invalid())?),
+            "YellowDepth" => self.yellow_depth = Some(value.parse().map_err(|_| invalid())?),
+            "Date" => self.date = Some(value.to_string()),
+            "Size" => {
+                let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+                let (width, height) = (width.parse().map_err(|_| invalid())?, height.parse().map_err(|_| invalid())?);
+                self.dims = Dimensions::new(width, height).and_then(|dims| dims.with_connect(self.dims.connect)).ok_or_else(invalid)?;
+            }
+            "Variant" => self.variant = Some(rules_from_name(value).ok_or_else(invalid)?.name().to_string()),
+            "Connect" => self.dims = self.dims.with_connect(value.parse().map_err(|_| invalid())?).ok_or_else(invalid)?,
+            "Result" => self.result = Self::parse_result(value).ok_or_else(invalid)?,
+            _ => () // unknown tags are ignored
+        }
+        Ok(())
+    }
+
+    /// Attaches a `{...}` comment to the last move, extracting the `[%eval score]` annotation.
+    fn parse_comment(&mut self, comment: &str) -> Result<(), RecordError> {
+        let Some(last) = self.moves.last_mut() else {
+            return Ok(());
+        };
+
+        let mut text = comment.trim();
+        if let Some(rest) = text.strip_prefix("[%eval ") {
+            let (score, rest) = rest.split_once(']').ok_or_else(|| RecordError::InvalidScore(comment.to_string()))?;
+            last.score = Some(score.trim().parse().map_err(|_| RecordError::InvalidScore(comment.to_string()))?);
+            text = rest.trim();
+        }
+
+        if !text.is_empty() {
+            last.comment = Some(text.to_string());
+        }
+        Ok(())
+    }
+
+    fn parse_moves(&mut self, movetext: &str) -> Result<(), RecordError> {
+        let mut rest = movetext.trim_start();
+        while !rest.is_empty() {
+            if let Some(comment) = rest.strip_prefix('{') {
+                let end = comment.find('}').ok_or(RecordError::UnclosedComment)?;
+                self.parse_comment(&comment[..end])?;
+                rest = comment[end + 1..].trim_start();
+                continue;
+            }
+
+            let end = rest.find(|c: char| c.is_whitespace() || c == '{').unwrap_or(rest.len());
+            let token = &rest[..end];
+            rest = rest[end..].trim_start();
+
+            if token.ends_with('.') || Self::parse_result(token).is_some() {
+                continue; // move numbers and the final result
+            }
+
+            let mv = token.parse().map_err(|_| RecordError::InvalidMove(token.to_string()))?;
+            self.moves.push(RecordedMove::new(mv));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Red \"{}\"]", self.red)?;
+        writeln!(f, "[Yellow \"{}\"]", self.yellow)?;
+        if let Some(depth) = self.red_depth {
+            writeln!(f, "[RedDepth \"{}\"]", depth)?;
+        }
+        if let Some(depth) = self.yellow_depth {
+            writeln!(f, "[YellowDepth \"{}\"]", depth)?;
+        }
+        if let Some(date) = &self.date {
+            writeln!(f, "[Date \"{}\"]", date)?;
+        }
+        if (self.dims.width, self.dims.height) != (WIDTH, HEIGHT) {
+            writeln!(f, "[Size \"{}\"]", self.dims)?;
+        }
+        if self.dims.connect != CONNECT {
+            writeln!(f, "[Connect \"{}\"]", self.dims.connect)?;
+        }
+        if let Some(variant) = &self.variant {
+            writeln!(f, "[Variant \"{}\"]", variant)?;
+        }
+        writeln!(f, "[Result \"{}\"]", self.result_str())?;
+        writeln!(f)?;
+
+        for (index, recorded) in self.moves.iter().enumerate() {
+            if index % 2 == 0 {
+                write!(f, "{}. ", index / 2 + 1)?;
+            }
+            write!(f, "{} ", recorded.mv)?;
+
+            match (recorded.score, &recorded.comment) {
+                (Some(score), Some(comment)) => write!(f, "{{[%eval {}] {}}} ", score, comment)?,
+                (Some(score), None) => write!(f, "{{[%eval {}]}} ", score)?,
+                (None, Some(comment)) => write!(f, "{{{}}} ", comment)?,
+                (None, None) => ()
+            }
+        }
+        writeln!(f, "{}", self.result_str())
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::default();
+        let mut movetext = String::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('[') && movetext.trim().is_empty() {
+                record.parse_tag(line)?;
+            }
+            else {
+                movetext += line;
+                movetext.push('\n');
+            }
+        }
+
+        record.parse_moves(&movetext)?;
+        Ok(record)
+    }
+}
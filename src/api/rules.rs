@@ -0,0 +1,188 @@
+use core::fmt;
+use std::sync::Arc;
+
+use crate::api::engine::{Color, Dimensions, Game, GameOutcome};
+
+// discs a player must collect to win a game of Pop Ten
+pub const POP_TEN_TARGET: i32 = 10;
+
+/// Rule set of a [Game]: which moves are legal and when the game ends.
+///
+/// Every method has the behaviour of plain Connect Four by default.
+pub trait Rules: fmt::Debug + Send + Sync {
+    /// Name of the rule set, used by the variant tag of records.
+    fn name(&self) -> &'static str;
+
+    /// Dimensions of the board, given the ones requested for the game.
+    fn dimensions(&self, dims: Dimensions) -> Dimensions {
+        dims
+    }
+
+    /// Plays the tokens on the board before the first move of the players.
+    fn setup(&self, _game: &mut Game) {}
+
+    fn can_push(&self, game: &Game, col: i32) -> bool {
+        game.board.bitboard & game.dims().bit(col, game.dims().height - 1) == 0
+    }
+
+    fn can_pop(&self, _game: &Game, _col: i32) -> bool {
+        false
+    }
+
+    /// Whether the token popped from the column goes back on top of it instead of leaving the board.
+    fn pop_returns_token(&self, _game: &Game, _col: i32) -> bool {
+        false
+    }
+
+    /// Whether the players first fill the board, the moves allowed afterwards depending on it. The keys of the
+    /// positions then tell the two phases apart, see [Game::board_filled].
+    fn fills_board_first(&self) -> bool {
+        false
+    }
+
+    /// Whether players only push, on any column that isn't full, and the first alignment ends the game,
+    /// which the move generation and the threat helpers such as [Game::non_losing_moves] rely on.
+    fn push_only(&self) -> bool {
+        true
+    }
+
+    /// Outcome of the game after the last move, the side to move being the next player.
+    fn outcome(&self, game: &Game) -> GameOutcome {
+        match game.check_win() {
+            Some(color) => GameOutcome::Win(color),
+            None if game.board.bitboard == game.dims().full() && game.get_possible_moves().is_empty() => GameOutcome::Draw,
+            None => GameOutcome::Ongoing
+        }
+    }
+}
+
+/// Plain Connect Four.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Standard;
+
+impl Rules for Standard {
+    fn name(&self) -> &'static str {
+        "Standard"
+    }
+}
+
+/// Connect Four where a player may instead pop one of their tokens from the bottom row, see [Game::make_pop].
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PopOut;
+
+impl Rules for PopOut {
+    fn name(&self) -> &'static str {
+        "PopOut"
+    }
+
+    fn can_pop(&self, game: &Game, col: i32) -> bool {
+        game.board.color_bitboard & game.dims().bit(col, 0) != 0
+    }
+
+    fn push_only(&self) -> bool {
+        false
+    }
+}
+
+/// Pop Ten, simplified to one move per turn.
+///
+/// Players first fill the board without any alignment ending the game, then pop their own tokens from the bottom row.
+/// A popped token that was part of an alignment of its color is collected, otherwise it goes back on top of its
+/// column. The first player to collect [POP_TEN_TARGET] tokens wins, the game is a draw when the next player can't pop.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PopTen;
+
+impl PopTen {
+    fn filling(game: &Game) -> bool {
+        !game.board_filled()
+    }
+
+    /// Tokens collected by the player, as many as are missing from the board once it has been filled.
+    pub fn collected(game: &Game, color: Color) -> i32 {
+        let squares = game.dims().squares();
+        let tokens = match color {
+            Color::Red => (squares + 1) / 2,
+            Color::Yellow => squares / 2
+        };
+        tokens - game.tokens(color).count_ones() as i32
+    }
+}
+
+impl Rules for PopTen {
+    fn name(&self) -> &'static str {
+        "PopTen"
+    }
+
+    fn can_push(&self, game: &Game, col: i32) -> bool {
+        Self::filling(game) && Standard.can_push(game, col)
+    }
+
+    fn can_pop(&self, game: &Game, col: i32) -> bool {
+        !Self::filling(game) && game.board.color_bitboard & game.dims().bit(col, 0) != 0
+    }
+
+    fn pop_returns_token(&self, game: &Game, col: i32) -> bool {
+        let aligned = game.aligned_squares(game.tokens(game.turn_color));
+        aligned & game.dims().bit(col, 0) == 0
+    }
+
+    fn fills_board_first(&self) -> bool {
+        true
+    }
+
+    fn push_only(&self) -> bool {
+        false
+    }
+
+    fn outcome(&self, game: &Game) -> GameOutcome {
+        if Self::filling(game) {
+            return GameOutcome::Ongoing;
+        }
+
+        let player = game.turn_color.toggle();
+        if Self::collected(game, player) >= POP_TEN_TARGET {
+            GameOutcome::Win(player)
+        }
+        else if game.get_possible_moves().is_empty() {
+            GameOutcome::Draw
+        }
+        else {
+            GameOutcome::Ongoing
+        }
+    }
+}
+
+/// Five in a row on a 9x6 board, the first and last columns being filled with alternating colors before the game.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct FiveInARow;
+
+impl Rules for FiveInARow {
+    fn name(&self) -> &'static str {
+        "FiveInARow"
+    }
+
+    fn dimensions(&self, _dims: Dimensions) -> Dimensions {
+        Dimensions::new(9, 6).and_then(|dims| dims.with_connect(5)).unwrap()
+    }
+
+    /// Red starts the first column and yellow the last one, then red moves.
+    fn setup(&self, game: &mut Game) {
+        let last = game.dims().width - 1;
+        for _ in 0..game.dims().height / 2 {
+            for col in [0, last, last, 0] {
+                game.make_push(col);
+            }
+        }
+    }
+}
+
+/// Returns the rule set of the given [Rules::name].
+pub fn rules_from_name(name: &str) -> Option<Arc<dyn Rules>> {
+    match name {
+        "Standard" => Some(Arc::new(Standard)),
+        "PopOut" => Some(Arc::new(PopOut)),
+        "PopTen" => Some(Arc::new(PopTen)),
+        "FiveInARow" => Some(Arc::new(FiveInARow)),
+        _ => None
+    }
+}
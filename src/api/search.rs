@@ -1,112 +1,59 @@
-use std::{cmp::max, collections::HashMap, sync::{Arc, Mutex}};
-use ux::u42;
+use core::fmt;
+use std::{cmp::{max, Reverse}, collections::HashMap, sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}}, thread::{self, JoinHandle}, time::{Duration, Instant}};
 
-use crate::api::engine::{Board, File, Game};
+use crate::api::{engine::{Bitboard, Board, Color, Game, GameOutcome, Move, DIRECTIONS}, record::GameRecord, tt::{NodeType, TTEntry, TranspositionTable}};
 use rayon::prelude::*;
 
-#[derive(Default, Clone)]
-enum NodeType {
-    EXACT,
-    LOWERBOUND,
-    UPPERBOUND,
-    #[default]
-    None
-}
-
-#[derive(Default, Clone)]
-pub struct TTEntry {
-    flag: NodeType,
-    depth: i32,
-    value: i32
-}
-
-impl TTEntry {
-    pub fn new() -> Self {
-        Self {..Default::default()}
-    }
-}
-
 pub struct Evaluation;
 
 impl Evaluation {
-    fn evaluate_window(window: u42, board: &Board) -> i32 {
-        // println!("evaluating window {:042b}", window);
-        // display_u42(window);
-        // println!("\n");
-        let (red, yellow): (u42, u42) = match board.history.len() & 1 {
-            0 => (board.color_bitboard, board.color_bitboard ^ board.bitboard),
-            1 => (board.color_bitboard ^ board.bitboard, board.color_bitboard),
-            _ => unreachable!(),
-        };
+    fn evaluate_window(window: Bitboard, red: Bitboard, yellow: Bitboard, connect: i32) -> i32 {
+        let red_count = (window & red).count_ones() as i32;
+        let yellow_count = (window & yellow).count_ones() as i32;
 
-        let red_count_raw: u64 = (window & red).into();
-        let red_count = red_count_raw.count_ones();
-        let yellow_count_raw: u64 = (window & yellow).into();
-        let yellow_count = yellow_count_raw.count_ones();
-
-        let empty_count = 4 - red_count - yellow_count; // mask to get 0-4 range
-        
-        // If both colors in window, it's blocked - score 0
-        if red_count > 0 && yellow_count > 0 {
+        // If both colors in window, it's blocked - score 0, same for an empty window
+        if (red_count > 0) == (yellow_count > 0) {
             return 0;
         }
 
-        let score = match (red_count, yellow_count, empty_count) {
-            // Red (current player) patterns
-            (4, 0, 0) => 100_000,      // Win
-            (3, 0, 1) => 100,          // Three in a row with empty
-            (2, 0, 2) => 10,           // Two in a row with 2 empty
-            (1, 0, 3) => 1,            // One with 3 empty
-            
-            // Yellow (opponent) patterns - negative scores
-            (0, 4, 0) => -100_000,     // Opponent win
-            (0, 3, 1) => -100,         // Opponent threat
-            (0, 2, 2) => -10,          // Opponent two in row
-            (0, 1, 3) => -1,           // Opponent one piece
-            
-            _ => 0,
+        // Red (current player) patterns are positive, Yellow (opponent) ones negative
+        let (count, sign) = if red_count > 0 { (red_count, 1) } else { (yellow_count, -1) };
+        // evaluated positions are ongoing, so a completed window isn't a win, as while filling the board of Pop Ten
+        let score = match connect - count {
+            1 => 100,     // one token missing
+            2 => 10,      // two tokens missing
+            3 => 1,       // three tokens missing
+            _ => 0
         };
-
-        score
+        sign * score
     }
 
     fn evaluation_window(board: &Board) -> i32 {
+        let dims = board.dims;
+        let length = dims.connect - 1;
+        let (red, yellow) = match board.history.len() & 1 {
+            0 => (board.color_bitboard, board.color_bitboard ^ board.bitboard),
+            1 => (board.color_bitboard ^ board.bitboard, board.color_bitboard),
+            _ => unreachable!(),
+        };
+
         let mut score = 0i32;
-        
-        // horizontals
-        let mut window = u42::new(0b1111);
-        for _ in 0..6 {
-            for _ in 0..3 {
-                score += Self::evaluate_window(window, &board);
-                window <<= 1;
+        for (dx, dy) in DIRECTIONS {
+            if length * dx >= dims.width || length * dy.abs() >= dims.height {
+                continue;
             }
-            score += Self::evaluate_window(window, &board);
-            window <<= 4;
-        }
-        
-        // verticals
-        let mut window = u42::new(0x204081);
-        for _ in 0..21 {
-            score += Self::evaluate_window(window, &board);
-            window <<= 1;
-        }
-        
-        // diags
-        let mut ascend_diag_window = u42::new(0x1010101); 
-        let mut descend_diag_window = u42::new(0x208208); 
-        for _ in 0..3 {
-            for _ in 0..3 {
-                score += Self::evaluate_window(ascend_diag_window, &board);
-                score += Self::evaluate_window(descend_diag_window, &board);
-                ascend_diag_window <<= 1;
-                descend_diag_window <<= 1;
+
+            // first window of the direction, starting from the bottom left corner or above it for descending diagonals
+            let first_row = if dy < 0 { length } else { 0 };
+            let window = (0..dims.connect).fold(0, |window, k| window | dims.bit(k * dx, first_row + k * dy));
+
+            for row in 0..dims.height - length * dy.abs() {
+                for col in 0..dims.width - length * dx {
+                    score += Self::evaluate_window(window << (col * dims.stride() + row), red, yellow, dims.connect);
+                }
             }
-            score += Self::evaluate_window(ascend_diag_window, &board);
-            score += Self::evaluate_window(descend_diag_window, &board);
-            ascend_diag_window <<= 4;
-            descend_diag_window <<= 4;
         }
-        
+
         score
     }
 
@@ -114,138 +61,530 @@ impl Evaluation {
         let mut score = 0;
         score += Evaluation::evaluation_window(board);
 
-        let center_mask = File::D.mask();
-        let center_pieces_raw: u64 = (board.color_bitboard & center_mask).into();
-        let center_pieces = center_pieces_raw.count_ones() as i32;
+        let center_mask = board.dims.column_mask(board.dims.width / 2);
+        let center_pieces = (board.color_bitboard & center_mask).count_ones() as i32;
         score += center_pieces * 3;
 
-        score
+        // heuristic scores must not be mistaken for wins
+        score.clamp(-MAX_HEURISTIC_SCORE, MAX_HEURISTIC_SCORE)
+    }
+}
+
+/// Score of a win with the `n`-th token of the board, `WIN_SCORE - n`.
+///
+/// Since the distance is counted from the start of the game rather than from the root of the search, a score only
+/// depends on the position and can be stored as is in the transposition table.
+pub const WIN_SCORE: i32 = 1_000_000;
+
+/// Longest game whose wins are scored apart from the heuristic, games with pops can last longer than the number
+/// of squares.
+pub const MAX_PLY: i32 = 10_000;
+
+// bound of the scores of Evaluation::evaluate, below the score of a win at MAX_PLY
+const MAX_HEURISTIC_SCORE: i32 = WIN_SCORE - MAX_PLY - 1;
+
+/// Outcome of [Search::think], scores are given from the point of view of the side to move.
+#[derive(Default, Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub value: Option<GameValue>, // forced win or loss found by the search, if any
+    pub pv: Vec<Move>, // principal variation, starting with best_move
+    pub root_scores: Vec<(Move, i32)>, // score of every root move
+    pub depth: i32, // depth of the last completed iteration
+    pub nodes: u64,
+    pub elapsed: Duration
+}
+
+/// Shared flag used to interrupt a running [Search] from another thread.
+///
+/// Once raised, the flag stays up and aborts every search sharing it until [StopHandle::clear] is called.
+#[derive(Default, Clone)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
     }
 }
 
+/// A [Search] running in the background on the opponent's time, see [Search::ponder].
+pub struct Ponder {
+    stop: StopHandle,
+    handle: JoinHandle<Search>
+}
+
+impl Ponder {
+    /// Interrupts the background search and gives back the [Search] with its warmed up transposition table.
+    pub fn stop(self) -> Search {
+        self.stop.stop();
+        let search = self.handle.join().unwrap();
+        self.stop.clear();
+        search
+    }
+}
+
+/// Iterative deepening negamax search.
+///
+/// [Search::think] deepens one ply at a time up to `depth`, and stops early once `time_limit` or `node_limit` is exceeded,
+/// in which case the best move of the last completed iteration is kept.
 #[derive(Default, Clone)]
 pub struct Search {
     pub depth: i32,
-    pub tt: HashMap<u64, TTEntry> // zobrist_key, TTEntry
+    pub time_limit: Option<Duration>,
+    pub node_limit: Option<u64>,
+    pub tt: Arc<TranspositionTable>, // shared by every search thread, indexed by canonical_key
+    nodes: Arc<AtomicU64>, // shared by every thread of the current think
+    stop: StopHandle,
+    aborted: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    max_nodes: Option<u64>,
+    killers: Vec<[Option<i32>; 2]>, // two moves per ply that caused a beta cutoff
+    history: Vec<[i32; 2]> // cutoff counts per move index and side, see Move::index
 }
 
 impl Search {
     pub fn new(depth: i32) -> Self {
         Self { 
-            depth: depth,
+            depth,
             ..Default::default()
         }
     }
 
-    fn negamax(&mut self, game: &mut Game, depth: i32, mut alpha: i32, beta: i32, color: i32) -> i32 {
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn with_node_limit(mut self, node_limit: u64) -> Self {
+        self.node_limit = Some(node_limit);
+        self
+    }
+
+    /// Replaces the transposition table by an empty one of `size_mb` megabytes.
+    pub fn with_tt_size(mut self, size_mb: usize) -> Self {
+        self.tt = Arc::new(TranspositionTable::new(size_mb));
+        self
+    }
+
+    /// Returns a handle that can abort [Search::think] from another thread.
+    /// If no iteration completed before the stop, the `best_move` returned by `think` is `None`.
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    /// Starts searching the position in a background thread without time nor node limits,
+    /// until [Ponder::stop] is called or the maximum depth is reached.
+    pub fn ponder(mut self, game: &Game) -> Ponder {
+        let stop = self.stop.clone();
+        let mut game = game.clone();
+        let handle = thread::spawn(move || {
+            let time_limit = self.time_limit.take();
+            let node_limit = self.node_limit.take();
+            self.think(&mut game);
+            self.time_limit = time_limit;
+            self.node_limit = node_limit;
+            self
+        });
+
+        Ponder { stop, handle }
+    }
+
+    /// Number of nodes searched during the last call to [Search::think].
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    /// Counts a new node and checks whether the time or node budget is exhausted.
+    fn out_of_budget(&self) -> bool {
+        if self.aborted.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        if self.stop.is_stopped() {
+            self.aborted.store(true, Ordering::Relaxed);
+            return true;
+        }
+
+        let nodes = self.nodes.fetch_add(1, Ordering::Relaxed) + 1;
+        let exceeded = self.max_nodes.is_some_and(|max_nodes| nodes > max_nodes)
+            || (nodes & 1023 == 0 && self.deadline.is_some_and(|deadline| Instant::now() >= deadline));
+
+        if exceeded {
+            self.aborted.store(true, Ordering::Relaxed);
+        }
+        exceeded
+    }
+
+    /// Negamax with alpha-beta pruning, `pv` is filled with the best line found from this node.
+    fn negamax(&mut self, game: &mut Game, depth: i32, mut alpha: i32, beta: i32, color: i32, pv: &mut Vec<i32>) -> i32 {
         let alpha_orig = alpha;
+        pv.clear();
         
-        if let Some(tt_entry) = self.tt.get(&game.zobrist_key) && tt_entry.depth >= depth {
+        if self.out_of_budget() {
+            return 0;
+        }
+
+        // mirrored positions share their entry, the best move is stored for the canonical side
+        let width = game.dims().width;
+        let mirrored = game.is_mirrored();
+        let mirror = |m: i32| if mirrored { Move::from_index(m, width).mirrored(width).index(width) } else { m };
+        let tt_entry = self.tt.probe(game.canonical_key());
+        if let Some(tt_entry) = tt_entry && tt_entry.depth >= depth {
             match tt_entry.flag {
-                NodeType::EXACT => return tt_entry.value,
-                NodeType::LOWERBOUND if tt_entry.value >= beta => return tt_entry.value,
-                NodeType::UPPERBOUND if tt_entry.value <= alpha => return tt_entry.value,
+                NodeType::Exact => return tt_entry.value,
+                NodeType::LowerBound if tt_entry.value >= beta => return tt_entry.value,
+                NodeType::UpperBound if tt_entry.value <= alpha => return tt_entry.value,
                 _ => ()
             }
         }
 
-        if depth == 0 || game.winner.is_some() {
+        let ply = game.board.history.len();
+        match game.outcome {
+            // a pop can make the side to move win
+            GameOutcome::Win(color) if color == game.turn_color => return WIN_SCORE - ply as i32,
+            GameOutcome::Win(_) => return -(WIN_SCORE - ply as i32),
+            GameOutcome::Draw => return 0,
+            GameOutcome::Ongoing => ()
+        }
+
+        if depth == 0 {
             return color * Evaluation::evaluate(&game.board);
         }
 
-        let mut child_nodes = game.get_possible_moves();
-        child_nodes.sort_by_key(|&m| (m - 3).abs());
+        let side = (color < 0) as usize;
+        // games with pops can last longer than the number of squares
+        if ply >= self.killers.len() {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let tt_move = tt_entry.and_then(|tt_entry| tt_entry.best_move).map(mirror);
+
+        // moves giving the opponent an immediate win are pruned, unless every move does
+        // the threats only consider pushes, so nothing is pruned when the rules allow other moves
+        let mut child_nodes = match game.rules.push_only() {
+            true => game.non_losing_moves(),
+            false => Vec::new()
+        };
+        if child_nodes.is_empty() {
+            child_nodes = game.get_possible_moves();
+        }
+
+        // transposition table move first, then killers, then history, then closest to the center
+        child_nodes.sort_by_key(|&m| Reverse((
+            Some(m) == tt_move,
+            self.killers[ply].contains(&Some(m)),
+            self.history[m as usize][side],
+            -game.dims().center_distance(m % width)
+        )));
         
         let mut best_score = i32::MIN;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
 
         for child in child_nodes {
-            game.make_push(child);
-            best_score = max(best_score, self.negamax(game, depth - 1, beta.saturating_neg(), alpha.saturating_neg(), -color).saturating_neg());
-            game.unmake_push();
+            game.make_move(child);
+            let score = self.negamax(game, depth - 1, beta.saturating_neg(), alpha.saturating_neg(), -color, &mut child_pv).saturating_neg();
+            game.unmake_move();
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(child);
+                pv.clear();
+                pv.push(child);
+                pv.append(&mut child_pv);
+            }
 
             alpha = max(alpha, best_score);
             if alpha >= beta {
+                if self.killers[ply][0] != Some(child) {
+                    self.killers[ply] = [Some(child), self.killers[ply][0]];
+                }
+                self.history[child as usize][side] += depth * depth;
                 break;
             }
         }
 
+        // scores of an interrupted search are meaningless and must not pollute the table
+        if self.aborted.load(Ordering::Relaxed) {
+            return 0;
+        }
+
         let mut tt_entry = TTEntry::new();
         if best_score <= alpha_orig {
-            tt_entry.flag = NodeType::UPPERBOUND;
+            tt_entry.flag = NodeType::UpperBound;
         }
         else if best_score >= beta {
-            tt_entry.flag = NodeType::LOWERBOUND;
+            tt_entry.flag = NodeType::LowerBound;
         }
         else {
-            tt_entry.flag = NodeType::EXACT;
+            tt_entry.flag = NodeType::Exact;
         }
 
         tt_entry.depth = depth;
         tt_entry.value = best_score;
-        self.tt.insert(game.zobrist_key, tt_entry);
+        tt_entry.best_move = best_move.map(mirror);
+        self.tt.store(game.canonical_key(), tt_entry);
 
-        return best_score;
+        best_score
     }
 
-    pub fn think(&mut self, game: &mut Game) -> Option<i32> {
+    /// Searches every root move at the given depth,
+    /// or returns `None` if the search was interrupted before the iteration completed.
+    fn think_depth(&mut self, game: &Game, depth: i32) -> Option<SearchResult> {
         let all_moves = game.get_possible_moves();
-        let best_move= Arc::new(Mutex::new(None));
-        let best_score = Arc::new(Mutex::new(i32::MIN));
-
-        let all_moves = all_moves.as_slice();
-        all_moves.par_iter().for_each(|_move| {
+        let results: Vec<(i32, i32, Vec<i32>)> = all_moves.par_iter().map(|&_move| {
             let mut game_copy = game.clone();
             let mut search_copy = self.clone();
+            let mut pv = Vec::new();
 
-            game_copy.make_push(*_move);
+            game_copy.make_move(_move);
             let color = game_copy.turn_color.to_int();
-            let move_score = search_copy.negamax(&mut game_copy, search_copy.depth, i32::MIN, i32::MAX, color).saturating_neg();
-            game_copy.unmake_push();
-            
-            let mut score_guard = best_score.lock().unwrap();
-            if move_score > *score_guard {
-                *score_guard = move_score;
-                *best_move.lock().unwrap() = Some(*_move);
+            let move_score = search_copy.negamax(&mut game_copy, depth, i32::MIN, i32::MAX, color, &mut pv).saturating_neg();
+            (_move, move_score, pv)
+        }).collect();
+
+        let width = game.dims().width;
+        let mut result = SearchResult { depth, ..Default::default() };
+        let mut best_score = i32::MIN;
+        for (_move, move_score, pv) in results {
+            result.root_scores.push((Move::from_index(_move, width), move_score));
+
+            if result.best_move.is_none() || move_score > best_score {
+                best_score = move_score;
+                result.best_move = Some(Move::from_index(_move, width));
+                result.score = move_score;
+                result.pv = [_move].iter().chain(&pv).map(|&m| Move::from_index(m, width)).collect();
             }
-        });
+        }
 
-        *best_move.lock().unwrap()
+        if self.aborted.load(Ordering::Relaxed) {
+            return None;
+        }
+        Some(result)
+    }
+
+    /// Converts a score found at `ply` into the forced result it proves, if it is a win or loss score.
+    fn mate_value(score: i32, ply: i32) -> Option<GameValue> {
+        // a win can't be scored before the current ply nor past MAX_PLY
+        let win_ply = WIN_SCORE.checked_sub(score.checked_abs()?)?;
+        if !(ply..=MAX_PLY).contains(&win_ply) {
+            return None;
+        }
+
+        match score.signum() {
+            1 => Some(GameValue::Win((win_ply - ply + 1) / 2)),
+            _ => Some(GameValue::Loss((win_ply - ply) / 2))
+        }
+    }
+
+    /// Searches the position and reports the result of the deepest completed iteration.
+    pub fn think(&mut self, game: &mut Game) -> SearchResult {
+        let start = Instant::now();
+        game.track_mirror();
+        self.nodes = Arc::new(AtomicU64::new(0));
+        self.aborted = Arc::new(AtomicBool::new(false));
+        self.deadline = None;
+        self.max_nodes = None;
+        self.killers = vec![[None; 2]; (game.dims().squares() + 1) as usize];
+        self.history = vec![[0; 2]; 2 * game.dims().width as usize];
+
+        // unless stopped, the first iteration always completes so that a move is available
+        let mut result = self.think_depth(game, 0).unwrap_or_default();
+        self.deadline = self.time_limit.map(|time_limit| start + time_limit);
+        self.max_nodes = self.node_limit;
+
+        for depth in 1..=self.depth {
+            match self.think_depth(game, depth) {
+                Some(iteration) => result = iteration,
+                None => break
+            }
+        }
+
+        result.value = Self::mate_value(result.score, game.board.history.len() as i32);
+        result.nodes = self.nodes();
+        result.elapsed = start.elapsed();
+        result
     }
 
     pub fn test_nets(depth1: i32, depth2: i32) {
         let mut game = Game::new();
         let mut search1 = Search::new(depth1);
         let mut search2 = Search::new(depth2);
-        let mut move_history = String::new();
-        loop {
-            if game.board.bitboard == u42::MAX {
-                println!("Tie");
-                break;
+        let mut record = GameRecord::new("Negamax", "Negamax");
+        record.red_depth = Some(depth1);
+        record.yellow_depth = Some(depth2);
+        record.date = Some(GameRecord::today());
+        while !game.outcome.is_over() {
+            let search = match game.turn_color {
+                Color::Red => &mut search1,
+                Color::Yellow => &mut search2
+            };
+
+            let result = search.think(&mut game);
+            if let Some(best_move) = result.best_move {
+                game.make_move(best_move.index(game.dims().width));
+                record.push(best_move, Some(result.score), None);
+            }
+        }
+        record.result = game.outcome;
+
+        match game.outcome {
+            GameOutcome::Win(Color::Red) => println!("Red won!"),
+            GameOutcome::Win(Color::Yellow) => println!("Yellow won!"),
+            _ => println!("Tie")
+        }
+        
+        game.board.display_board();
+        println!("{}", record);
+    }
+}
+/// Game-theoretic value of a position from the point of view of the side to move.
+///
+/// Move counts only include the moves of the winning side, the winning token included.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameValue {
+    Win(i32),
+    Loss(i32),
+    Draw
+}
+
+impl GameValue {
+    /// Converts a [Solver] score into a [GameValue], `ply` being the number of tokens on a board of `squares` squares.
+    pub fn from_score(score: i32, ply: i32, squares: i32) -> Self {
+        match score.signum() {
+            1 => GameValue::Win((squares + 3 - ply) / 2 - score),
+            -1 => GameValue::Loss((squares + 2 - ply) / 2 + score),
+            _ => GameValue::Draw
+        }
+    }
+}
+
+impl fmt::Display for GameValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameValue::Win(n) => write!(f, "win in {}", n),
+            GameValue::Loss(n) => write!(f, "loss in {}", n),
+            GameValue::Draw => write!(f, "draw")
+        }
+    }
+}
+
+/// Perfect-play solver searching every position until the end of the game, for [push only](crate::api::rules::Rules::push_only) rules.
+///
+/// Scores follow the usual convention: a win with the `n`-th token of the board is worth `(squares + 2 - n) / 2`,
+/// the winning token being counted in `n`, a draw is worth 0 and a loss is negative.
+#[derive(Default, Clone)]
+pub struct Solver {
+    pub nodes: u64,
+    tt: HashMap<u64, i32> // canonical_key, upper bound of the score
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self { ..Default::default() }
+    }
+
+    fn negamax(&mut self, game: &mut Game, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        let ply = game.board.history.len() as i32;
+        let squares = game.dims().squares();
+
+        if game.get_possible_moves().is_empty() {
+            return 0;
+        }
+
+        if !game.winning_moves().is_empty() {
+            return (squares + 1 - ply) / 2;
+        }
+
+        // every move lets the opponent win with its next token
+        let mut child_nodes = game.non_losing_moves();
+        if child_nodes.is_empty() {
+            return -(squares - ply) / 2;
+        }
+
+        // we can't win with our next token, so the best we can hope for is to win with the one after
+        let mut upper_bound = (squares - 1 - ply) / 2;
+        if let Some(&value) = self.tt.get(&game.canonical_key()) {
+            upper_bound = value;
+        }
+
+        if beta > upper_bound {
+            beta = upper_bound;
+            if alpha >= beta {
+                return beta;
             }
+        }
 
-            if let Some(best_move) = search1.think(&mut game) {
-                game.make_push(best_move);
-                move_history += &best_move.to_string();
+        child_nodes.sort_by_key(|&m| game.dims().center_distance(m));
+        for child in child_nodes {
+            game.make_push(child);
+            let score = -self.negamax(game, -beta, -alpha);
+            game.unmake_push();
+
+            if score >= beta {
+                return score;
             }
+            alpha = max(alpha, score);
+        }
+
+        self.tt.insert(game.canonical_key(), alpha);
+        alpha
+    }
 
-            if let Some(best_move) = search2.think(&mut game) {
-                game.make_push(best_move);
-                move_history += &best_move.to_string();
+    /// Returns the exact score of the position for the side to move.
+    ///
+    /// Panics if the rules of the game allow pops, which the solver doesn't play.
+    pub fn score(&mut self, game: &mut Game) -> i32 {
+        assert!(game.rules.push_only(), "the solver only plays the push only rules, not {}", game.rules.name());
+        game.track_mirror();
+        let ply = game.board.history.len() as i32;
+        let squares = game.dims().squares();
+        if game.winner().is_some() {
+            // the previous player won with the token that was just played
+            return -(squares + 2 - ply) / 2;
+        }
+
+        // null window search narrowing [min, max] until the exact score is found
+        let mut min = -(squares - ply) / 2;
+        let mut max = (squares + 1 - ply) / 2;
+        while min < max {
+            let mut med = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
             }
-            
-            if let Some(winner) = game.winner {
-                match winner {
-                    super::engine::Color::Red => println!("Red won!"),
-                    super::engine::Color::Yellow => println!("Yellow won!"),
-                }
-                break;
+            else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+
+            let score = self.negamax(game, med, med + 1);
+            if score <= med {
+                max = score;
+            }
+            else {
+                min = score;
             }
         }
-        
-        game.board.display_board();
-        println!("{}", move_history);
-        println!("Red: Negamax(depth={})", depth1);
-        println!("Yellow: Negamax(depth={})", depth2);
-        println!();
+
+        min
     }
-}
\ No newline at end of file
+
+    /// Solves the position and returns its [GameValue] for the side to move.
+    pub fn solve(&mut self, game: &mut Game) -> GameValue {
+        let score = self.score(game);
+        GameValue::from_score(score, game.board.history.len() as i32, game.dims().squares())
+    }
+
+    pub fn reset(&mut self) {
+        self.tt.clear();
+        self.nodes = 0;
+    }
+}
@@ -0,0 +1,207 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// default size of the table used by a new Search
+const DEFAULT_SIZE_MB: usize = 16;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NodeType {
+    Exact,
+    LowerBound,
+    UpperBound,
+    #[default]
+    None
+}
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct TTEntry {
+    pub flag: NodeType,
+    pub depth: i32,
+    pub value: i32,
+    pub best_move: Option<i32>
+}
+
+impl TTEntry {
+    pub fn new() -> Self {
+        Self {..Default::default()}
+    }
+
+    /// Packs the entry in a single word: value in the lower 32 bits, then depth on 8 bits, flag on 2 bits
+    /// and best move on 8 bits (0 when there is none, column + 1 otherwise).
+    /// The flag of a stored entry is never [NodeType::None], so a packed entry is never 0.
+    fn pack(&self) -> u64 {
+        let flag: u64 = match self.flag {
+            NodeType::Exact => 1,
+            NodeType::LowerBound => 2,
+            NodeType::UpperBound => 3,
+            NodeType::None => 0
+        };
+
+        let best_move = self.best_move.map_or(0, |m| m as u64 + 1);
+
+        self.value as u32 as u64 | (self.depth.clamp(0, 255) as u64) << 32 | flag << 40 | best_move << 42
+    }
+
+    fn unpack(data: u64) -> Self {
+        let flag = match (data >> 40) & 0b11 {
+            1 => NodeType::Exact,
+            2 => NodeType::LowerBound,
+            3 => NodeType::UpperBound,
+            _ => NodeType::None
+        };
+
+        Self {
+            flag,
+            depth: ((data >> 32) & 0xff) as i32,
+            value: data as u32 as i32,
+            best_move: match (data >> 42) & 0xff {
+                0 => None,
+                m => Some(m as i32 - 1)
+            }
+        }
+    }
+}
+
+/// A lockless slot, the key is stored xored with the data so that a torn write is seen as a miss.
+#[derive(Default)]
+pub(crate) struct Slot {
+    key: AtomicU64,
+    data: AtomicU64
+}
+
+impl Slot {
+    pub(crate) fn load(&self) -> (u64, u64) {
+        let data = self.data.load(Ordering::Relaxed);
+        (self.key.load(Ordering::Relaxed) ^ data, data)
+    }
+
+    pub(crate) fn store(&self, key: u64, data: u64) {
+        self.key.store(key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+}
+
+/// Allocates the largest power of two number of `T` fitting in `size_mb` megabytes, at least one,
+/// so that a key is mapped to its index with a mask.
+pub(crate) fn allocate<T: Default>(size_mb: usize) -> Box<[T]> {
+    let max_len = (size_mb * 1024 * 1024 / size_of::<T>()).max(1);
+    (0..1usize << max_len.ilog2()).map(|_| T::default()).collect()
+}
+
+/// Each bucket keeps the deepest entry seen for its index, and the most recent one.
+#[derive(Default)]
+struct Bucket {
+    depth_preferred: Slot,
+    always_replace: Slot
+}
+
+/// Counters of a [TranspositionTable] since its creation or last [TranspositionTable::clear].
+#[derive(Default, Clone, Copy, Debug)]
+pub struct TTStats {
+    pub probes: u64,
+    pub hits: u64,
+    pub stores: u64,
+    pub collisions: u64 // stores that evicted an entry of another position
+}
+
+/// Fixed-capacity transposition table shared by every search thread without locks.
+pub struct TranspositionTable {
+    buckets: Box<[Bucket]>,
+    probes: AtomicU64,
+    hits: AtomicU64,
+    stores: AtomicU64,
+    collisions: AtomicU64
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIZE_MB)
+    }
+}
+
+impl TranspositionTable {
+    /// Creates a table using at most `size_mb` megabytes, rounded down to a power of two number of buckets.
+    pub fn new(size_mb: usize) -> Self {
+        Self {
+            buckets: allocate(size_mb),
+            probes: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+            collisions: AtomicU64::new(0)
+        }
+    }
+
+    fn bucket(&self, key: u64) -> &Bucket {
+        &self.buckets[key as usize & (self.buckets.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TTEntry> {
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        let bucket = self.bucket(key);
+
+        for slot in [&bucket.depth_preferred, &bucket.always_replace] {
+            let (slot_key, data) = slot.load();
+            if data != 0 && slot_key == key {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(TTEntry::unpack(data));
+            }
+        }
+        None
+    }
+
+    pub fn store(&self, key: u64, entry: TTEntry) {
+        self.stores.fetch_add(1, Ordering::Relaxed);
+        let bucket = self.bucket(key);
+        let data = entry.pack();
+
+        let (slot_key, slot_data) = bucket.depth_preferred.load();
+        let slot = if slot_data == 0 || slot_key == key || entry.depth >= TTEntry::unpack(slot_data).depth {
+            &bucket.depth_preferred
+        }
+        else {
+            &bucket.always_replace
+        };
+
+        let (slot_key, slot_data) = slot.load();
+        if slot_data != 0 && slot_key != key {
+            self.collisions.fetch_add(1, Ordering::Relaxed);
+        }
+        slot.store(key, data);
+    }
+
+    pub fn clear(&self) {
+        for bucket in self.buckets.iter() {
+            bucket.depth_preferred.store(0, 0);
+            bucket.always_replace.store(0, 0);
+        }
+
+        self.probes.store(0, Ordering::Relaxed);
+        self.hits.store(0, Ordering::Relaxed);
+        self.stores.store(0, Ordering::Relaxed);
+        self.collisions.store(0, Ordering::Relaxed);
+    }
+
+    /// Maximum number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * 2
+    }
+
+    /// Number of occupied entries, walks the whole table.
+    pub fn len(&self) -> usize {
+        self.buckets.iter()
+            .map(|bucket| (bucket.depth_preferred.load().1 != 0) as usize + (bucket.always_replace.load().1 != 0) as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> TTStats {
+        TTStats {
+            probes: self.probes.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+            collisions: self.collisions.load(Ordering::Relaxed)
+        }
+    }
+}
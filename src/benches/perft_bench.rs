@@ -1,24 +1,20 @@
-use criterion::{Criterion, criterion_group, criterion_main};
-use ux::u42;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 use std::hint::black_box;
 
-use puissance_4::api::{engine::Game, search::Search};
+use puissance_4::api::{engine::{Color, Dimensions, Game, Perft}, search::Search};
 
 pub fn test_nets(depth1: i32, depth2: i32) {
     let mut game = Game::new();
     let mut search1 = Search::new(depth1);
     let mut search2 = Search::new(depth2);
-    loop {
-        if let Some(best_move) = search1.think(&mut game) {
-            game.make_push(best_move);
-        }
-
-        if let Some(best_move) = search2.think(&mut game) {
-            game.make_push(best_move);
-        }
+    while !game.outcome.is_over() {
+        let search = match game.turn_color {
+            Color::Red => &mut search1,
+            Color::Yellow => &mut search2
+        };
 
-        if game.winner.is_some() || game.board.bitboard == u42::MAX {
-            break;
+        if let Some(best_move) = search.think(&mut game).best_move {
+            game.make_move(best_move.index(game.dims().width));
         }
     }
 }
@@ -31,6 +27,48 @@ fn criterion_benchmark(c: &mut Criterion) {
     group.bench_function("Negamax 8 vs Negamax 8", |b| {
         b.iter(|| test_nets(black_box(8), black_box(8)))
     });
+    group.bench_function("Negamax 10 from 4434", |b| {
+        b.iter(|| {
+            let mut game = Game::new();
+            game.make_push_bulk("4434");
+            Search::new(black_box(10)).think(&mut game)
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(10);
+    for depth in 8..=12 {
+        // the serial perft takes minutes past depth 10
+        if depth <= 10 {
+            group.bench_with_input(BenchmarkId::new("serial", depth), &depth, |b, &depth| {
+                b.iter(|| Perft::new().run(depth))
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("tt", depth), &depth, |b, &depth| {
+            b.iter(|| Perft::new().run_tt(depth))
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", depth), &depth, |b, &depth| {
+            b.iter(|| Perft::new().run_parallel(depth))
+        });
+        group.bench_with_input(BenchmarkId::new("parallel shared tt", depth), &depth, |b, &depth| {
+            b.iter(|| Perft::new().with_shared_table(16).run_parallel(depth))
+        });
+    }
+    group.finish();
+
+    // the same tree on boards fitting in a u64 or not, 7 tokens can't fill a column of 8x7
+    let mut group = c.benchmark_group("perft bitboard");
+    group.sample_size(10);
+    for (name, height) in [("8x7 u64", 7), ("8x8 u128", 8)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut perft = Perft::new();
+                perft.game = Game::with_dimensions(Dimensions::new(8, height).unwrap());
+                perft.run(black_box(7))
+            })
+        });
+    }
     group.finish();
 }
 
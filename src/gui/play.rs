@@ -1,5 +1,7 @@
 use console::Term;
 
+use crate::api::engine::Move;
+
 pub fn input_difficulty() -> i32 {
     let term = Term::stdout();
 
@@ -16,7 +18,7 @@ pub fn input_difficulty() -> i32 {
         };
 
         if let Ok(input) = resolved_input {
-            if input < 1 || input > 14 {
+            if !(1..=14).contains(&input) {
                 continue;
             } 
             return resolved_input.unwrap() as i32;
@@ -27,21 +29,21 @@ pub fn input_difficulty() -> i32 {
     }
 }
 
-pub fn play() -> i32 {
+pub fn play() -> Move {
     let term = Term::stdout();
     loop {
-        let resolved_input: Option<u32> = match term.read_char() {
+        let resolved_input: Option<Move> = match term.read_char() {
             Ok(c) => {
-                c.to_digit(10)
+                Move::try_from(c).ok()
             }
             Err(_) => {
-                println!("invalid input (0-6)");
+                println!("invalid input (1-7)");
                 None
             }
         };
 
-        if resolved_input.is_some() {
-            return resolved_input.unwrap() as i32;
+        if let Some(input) = resolved_input {
+            return input;
         }
         else {
             continue;
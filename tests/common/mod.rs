@@ -0,0 +1,16 @@
+// helpers shared by the test files, included with `mod common;`
+use puissance_4::api::engine::Game;
+
+/// Xorshift64, so that the random games and boards of the tests are the same on every run.
+pub fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// Plays one of the possible moves at random, the game must not be over.
+pub fn play_random_move(game: &mut Game, state: &mut u64) {
+    let moves = game.get_possible_moves();
+    game.make_move(moves[(xorshift(state) % moves.len() as u64) as usize]);
+}
@@ -1,25 +1,237 @@
-use puissance_4::api::engine::*;
+use std::{collections::HashMap, path::PathBuf};
+
+use puissance_4::api::{engine::*, record::GameRecord};
 use rstest::rstest;
 
-pub fn test_bulk(game: &mut Game, history: &str) -> Option<Color> {
-    game.make_push_bulk(history);
+pub fn test_record(record: &GameRecord) -> GameOutcome {
+    let game = record.replay().unwrap();
     game.board.display_board();
-    println!("{}", history);
+    println!("{}", record);
+
+    game.outcome
+}
+
+#[rstest]
+fn test_alignment(#[files("tests/records/*.p4n")] path: PathBuf) {
+    let record = GameRecord::read_from_file(path).unwrap();
+    assert_eq!(test_record(&record), record.result);
+}
+
+#[rstest]
+#[case("445566", vec![6, 2], vec![6, 2])] // open three on the bottom row
+#[case("44556", vec![], vec![])] // two opponent threats
+#[case("11223", vec![], vec![3])] // single threat to block
+#[case("2113372", vec![], vec![6, 5, 4, 2, 1, 0])] // never play under an opponent threat
+fn test_threats(#[case] history: &str, #[case] winning: Vec<i32>, #[case] non_losing: Vec<i32>) {
+    let mut game = Game::new();
+    game.make_push_bulk(history);
+
+    assert_eq!(game.winning_moves(), winning);
+    assert_eq!(game.non_losing_moves(), non_losing);
+}
+
+#[test]
+fn test_winning_squares_do_not_wrap() {
+    let mut game = Game::new();
+    // red tokens at the end of the first row and the start of the second
+    game.make_push_bulk("5161711");
+
+    assert_eq!(game.winning_squares(Color::Red) & (1 << 8), 0);
+}
+
+#[test]
+fn test_draw_outcome() {
+    let mut game = Game::new();
+    game.make_push_bulk("331317422446336465157624214762167173725555");
+    assert_eq!(game.outcome, GameOutcome::Draw);
+    assert!(game.get_possible_moves().is_empty());
+
+    game.unmake_push();
+    assert_eq!(game.outcome, GameOutcome::Ongoing);
+    assert_eq!(game.get_possible_moves(), vec![4]);
+}
+
+#[rstest]
+#[case("44", 7, MoveError::OutOfRange(7))]
+#[case("44", -1, MoveError::OutOfRange(-1))]
+#[case("444444", 3, MoveError::ColumnFull(3))]
+#[case("4455667", 0, MoveError::GameOver)]
+fn test_try_play_errors(#[case] history: &str, #[case] col: i32, #[case] expected: MoveError) {
+    let mut game = Game::new();
+    game.make_push_bulk(history);
+    let zobrist_key = game.zobrist_key;
+
+    assert_eq!(game.try_play(Move::from_col(col)), Err(expected));
+    assert_eq!(game.zobrist_key, zobrist_key);
+    assert_eq!(game.board.history.len(), history.len());
+}
+
+#[rstest]
+#[case("4a5", MoveError::InvalidNotation('a'))]
+#[case("4449444", MoveError::OutOfRange(8))]
+#[case("4404", MoveError::InvalidNotation('0'))]
+#[case("44444444", MoveError::ColumnFull(3))]
+fn test_try_play_bulk_rolls_back(#[case] history: &str, #[case] expected: MoveError) {
+    let mut game = Game::new();
+    game.make_push_bulk("12");
+    let zobrist_key = game.zobrist_key;
+
+    assert_eq!(game.try_play_bulk(history), Err(expected));
+    assert_eq!(game.zobrist_key, zobrist_key);
+    assert_eq!(game.board.history.len(), 2);
+    assert!(game.try_play_bulk("444444").is_ok());
+}
+
+#[rstest]
+#[case("1", Ok(0))]
+#[case("4", Ok(3))]
+#[case("7", Ok(6))]
+#[case("0", Err(MoveError::InvalidNotation('0')))]
+#[case("x", Err(MoveError::InvalidNotation('x')))]
+#[case("44", Err(MoveError::InvalidNotation('4')))]
+fn test_move_notation(#[case] notation: &str, #[case] expected: Result<i32, MoveError>) {
+    let parsed = notation.parse::<Move>();
+    assert_eq!(parsed.map(Move::col), expected);
+
+    if let Ok(mv) = parsed {
+        assert_eq!(mv.to_string(), notation);
+    }
+}
 
-    game.winner
+#[test]
+fn test_move_error_display_is_one_based() {
+    assert_eq!(MoveError::ColumnFull(3).to_string(), "column 4 is full");
+    assert_eq!(Move::parse_sequence("4453"), Ok(vec![Move::from_col(3), Move::from_col(3), Move::from_col(4), Move::from_col(2)]));
+}
+
+#[test]
+fn test_zobrist_keys_are_deterministic() {
+    let mut game = Game::new();
+    assert_eq!(game.zobrist_key, 0);
+
+    game.make_push_bulk("4");
+    assert_eq!(game.zobrist_key, 0x6a0398e528f0ae6a);
+    game.make_push_bulk("4");
+    assert_eq!(game.zobrist_key, 0x3e784625013d91c9);
+}
+
+fn collect_position_keys(game: &mut Game, depth: i32, keys: &mut HashMap<u64, String>) {
+    let position = game.to_position();
+    if let Some(other) = keys.insert(game.position_key(), position.clone()) {
+        assert_eq!(other, position);
+    }
+
+    if depth == 0 {
+        return;
+    }
+    for col in game.get_possible_moves() {
+        game.make_push(col);
+        collect_position_keys(game, depth - 1, keys);
+        game.unmake_push();
+    }
+}
+
+#[test]
+fn test_position_key() {
+    let mut game = Game::new();
+    assert_eq!(game.position_key(), 0b0000001_0000001_0000001_0000001_0000001_0000001_0000001);
+
+    // red in the center, yellow on top of it
+    game.make_push_bulk("44");
+    assert_eq!(game.position_key(), 0b0000001_0000001_0000001_0000101_0000001_0000001_0000001);
+
+    let mut keys = HashMap::new();
+    collect_position_keys(&mut Game::new(), 5, &mut keys);
+    assert_eq!(keys.len(), 1 + 7 + 49 + 238 + 1120 + 4263);
 }
 
 #[rstest]
-#[case("50500101010", Some(Color::Red))] // vertical
-#[case("6655443", Some(Color::Red))]
-#[case("111522234443", Some(Color::Yellow))] //horizontal
-#[case("3334344433655", Some(Color::Red))]
-#[case("6140166111015", None)]
-#[case("23365636636535655531210112221140", None)]
-#[case("112263364400", None)]
-#[case("45441432344223333210220550606", None)]
-#[case("3333544454344223066666611111105", Some(Color::Red))]
-fn test_alignment(#[case] history: &str, #[case] expected: Option<Color>) {
+#[case("1", "7")]
+#[case("4453", "4435")]
+#[case("2113372", "6775516")]
+fn test_mirrored_positions_share_canonical_key(#[case] history: &str, #[case] mirror_history: &str) {
+    let mut game = Game::new();
+    game.make_push_bulk(history);
+    let mut mirror = Game::new();
+    mirror.make_push_bulk(mirror_history);
+
+    let mirrored = game.mirrored();
+    assert_eq!(mirrored.zobrist_key, mirror.zobrist_key);
+    assert_eq!(game.board.mirrored().bitboard, mirror.board.bitboard);
+    assert_eq!(game.board.mirrored().color_bitboard, mirror.board.color_bitboard);
+
+    assert_eq!(game.canonical_key(), mirror.canonical_key());
+    assert_ne!(game.is_mirrored(), mirror.is_mirrored());
+    assert_eq!(Move::from_col(1).mirrored(7), Move::from_col(5));
+}
+
+#[test]
+fn test_symmetric_position_is_its_own_mirror() {
     let mut game = Game::new();
-    assert_eq!(test_bulk(&mut game, history), expected);
-}
\ No newline at end of file
+    game.make_push_bulk("44");
+    assert_eq!(game.canonical_key(), game.zobrist_key);
+    assert!(!game.is_mirrored());
+    assert_eq!(game.mirrored().to_position(), game.to_position());
+}
+
+#[rstest]
+#[case(4, 4, 9, 206904)]
+#[case(6, 5, 7, 279720)]
+#[case(8, 7, 8, 16553656)]
+fn test_perft_other_sizes(#[case] width: i32, #[case] height: i32, #[case] depth: i32, #[case] expected: u64) {
+    let mut perft = Perft::new();
+    perft.game = Game::with_dimensions(Dimensions::new(width, height).unwrap());
+    assert_eq!(perft.run(depth), expected);
+}
+
+#[rstest]
+#[case(8, 7, "5566778", GameOutcome::Win(Color::Red))] // along the last column
+#[case(9, 7, "8899112", GameOutcome::Ongoing)] // last two columns then first two of the row above
+#[case(6, 5, "1213141", GameOutcome::Win(Color::Red))]
+#[case(4, 4, "4321311144234232", GameOutcome::Draw)]
+fn test_outcome_other_sizes(#[case] width: i32, #[case] height: i32, #[case] history: &str, #[case] expected: GameOutcome) {
+    let mut game = Game::with_dimensions(Dimensions::new(width, height).unwrap());
+    game.make_push_bulk(history);
+    assert_eq!(game.outcome, expected);
+}
+
+#[test]
+fn test_unsupported_dimensions() {
+    assert!(Dimensions::new(10, 6).is_none());
+    assert!(Dimensions::new(8, 9).is_none());
+    assert!(Dimensions::new(7, 0).is_none());
+    assert!(Dimensions::new(1, i32::MAX).is_none());
+    assert!(Dimensions::new(9, 8).is_none());
+    assert_eq!(Dimensions::new(8, 8).unwrap().full().count_ones(), 64);
+    assert_eq!(Dimensions::new(1, 64).unwrap().full().count_ones(), 64);
+}
+
+#[rstest]
+#[case(7, 6, 3, "11223", GameOutcome::Win(Color::Red))]
+#[case(9, 7, 5, "1122334", GameOutcome::Ongoing)]
+#[case(9, 7, 5, "112233445", GameOutcome::Win(Color::Red))]
+#[case(9, 7, 6, "9182736451", GameOutcome::Ongoing)] // five in a row
+#[case(8, 7, 6, "4822245334338632455572672", GameOutcome::Win(Color::Red))] // six on a diagonal
+fn test_outcome_connect_n(#[case] width: i32, #[case] height: i32, #[case] connect: i32, #[case] history: &str, #[case] expected: GameOutcome) {
+    let dims = Dimensions::new(width, height).unwrap().with_connect(connect).unwrap();
+    let mut game = Game::with_dimensions(dims);
+    game.make_push_bulk(history);
+    assert_eq!(game.outcome, expected);
+}
+
+#[test]
+fn test_perft_connect_3() {
+    let mut perft = Perft::new();
+    perft.game = Game::with_dimensions(Dimensions::new(4, 4).unwrap().with_connect(3).unwrap());
+    assert_eq!(perft.run(7), 13148);
+}
+
+#[test]
+fn test_threats_connect_5() {
+    let mut game = Game::with_dimensions(Dimensions::new(9, 7).unwrap().with_connect(5).unwrap());
+    game.make_push_bulk("11223344");
+    assert_eq!(game.winning_moves(), vec![4]);
+    assert_eq!(Dimensions::new(7, 6).unwrap().with_connect(1), None);
+    assert_eq!(Dimensions::new(7, 6).unwrap().with_connect(8), None);
+    assert!(Dimensions::new(1, 64).unwrap().with_connect(64).is_some());
+}
@@ -0,0 +1,138 @@
+use puissance_4::api::{engine::*, rules::PopOut};
+use rstest::rstest;
+
+#[rstest]
+fn test_perft_known_counts(#[values(0, 1, 2, 3, 4, 5, 6)] depth: i32) {
+    assert_eq!(Perft::new().run(depth), PERFT_COUNTS[depth as usize]);
+}
+
+#[test]
+fn test_perft_tt_known_counts() {
+    // the table is kept between the depths
+    let mut perft = Perft::new();
+    for (depth, &expected) in PERFT_COUNTS.iter().enumerate().take(10) {
+        assert_eq!(perft.run_tt(depth as i32), expected);
+    }
+}
+
+#[rstest]
+#[case(Game::with_dimensions(Dimensions::new(4, 4).unwrap()), 9, 206904)]
+#[case(Game::with_dimensions(Dimensions::new(4, 4).unwrap().with_connect(3).unwrap()), 7, 13148)]
+#[case(Game::with_rules(PopOut, Dimensions::new(4, 4).unwrap()), 8, 288152)]
+fn test_perft_tt_matches_run(#[case] game: Game, #[case] depth: i32, #[case] expected: u64) {
+    let mut perft = Perft::new();
+    perft.game = game;
+    assert_eq!(perft.run_tt(depth), expected);
+    assert_eq!(perft.run(depth), expected);
+}
+
+#[test]
+fn test_perft_divide() {
+    let mut perft = Perft::new();
+    let counts = perft.divide(6);
+    assert_eq!(counts.iter().map(|&(mv, _)| mv.to_string()).collect::<Vec<_>>(), ["7", "6", "5", "4", "3", "2", "1"]);
+    assert_eq!(counts.iter().map(|&(_, nodes)| nodes).sum::<u64>(), PERFT_COUNTS[6]);
+    // mirrored columns have the same counts
+    assert!(counts.iter().zip(counts.iter().rev()).all(|(a, b)| a.1 == b.1));
+
+    perft.game.make_push_bulk("445566");
+    let counts = perft.divide(2);
+    assert_eq!(counts.len(), 7);
+    // the winning moves end the game
+    assert_eq!(counts.iter().filter(|&&(_, nodes)| nodes == 0).map(|&(mv, _)| mv.to_string()).collect::<Vec<_>>(), ["7", "3"]);
+}
+
+#[test]
+fn test_perft_negative_depth() {
+    let mut perft = Perft::new();
+    assert_eq!(perft.run(-1), 1);
+    assert_eq!(perft.run_tt(-1), 1);
+    assert_eq!(perft.run_parallel(-1), 1);
+    assert!(perft.divide(0).is_empty());
+    assert!(perft.divide(-1).is_empty());
+}
+
+#[rstest]
+fn test_perft_parallel(#[values(false, true)] shared: bool) {
+    let mut perft = match shared {
+        true => Perft::new().with_shared_table(4),
+        false => Perft::new()
+    };
+    for depth in 0..=7 {
+        assert_eq!(perft.run_parallel(depth), PERFT_COUNTS[depth as usize]);
+    }
+
+    // the shared table is cleared along with the game
+    perft.game = Game::with_rules(PopOut, Dimensions::new(4, 4).unwrap());
+    perft.reset();
+    assert_eq!(perft.run_parallel(8), 288152);
+    assert_eq!(perft.run_parallel(8), 288152);
+}
+
+#[test]
+fn test_perft_table_large_counts() {
+    let table = PerftTable::new(1);
+    table.store(42, 12, (1 << 56) - 1);
+    assert_eq!(table.probe(42, 12), Some((1 << 56) - 1));
+
+    // a count that doesn't fit isn't stored rather than truncated
+    table.store(42, 12, 1 << 56);
+    table.store(7, 12, u64::MAX);
+    assert_eq!(table.probe(42, 12), Some((1 << 56) - 1));
+    assert_eq!(table.probe(7, 12), None);
+    assert_eq!(table.probe(42, 11), None);
+}
+
+#[test]
+fn test_perft_stats() {
+    let stats = Perft::new().stats(7);
+    assert!(stats.iter().enumerate().all(|(depth, stats)| stats.total() == PERFT_COUNTS[depth]));
+    assert_eq!(stats[6], PerftStats { ongoing: 117649, ..Default::default() });
+    assert_eq!(stats[7], PerftStats { ongoing: 810504, red_wins: 13032, ..Default::default() });
+    assert!(Perft::new().stats(-1).is_empty());
+}
+
+#[test]
+fn test_perft_stats_full_board() {
+    let mut perft = Perft::new();
+    perft.game = Game::with_dimensions(Dimensions::new(4, 3).unwrap().with_connect(3).unwrap());
+    let stats = perft.stats(12);
+    assert_eq!(stats[11], PerftStats { ongoing: 30878, red_wins: 45774, ..Default::default() });
+    assert_eq!(stats[12], PerftStats { yellow_wins: 15286, draws: 15592, ..Default::default() });
+}
+
+#[test]
+fn test_perft_stats_popout() {
+    let mut perft = Perft::new();
+    perft.game = Game::with_rules(PopOut, Dimensions::new(4, 4).unwrap());
+    let stats = perft.stats(7);
+    assert!(stats.iter().enumerate().all(|(depth, stats)| stats.total() == perft.run(depth as i32)));
+    // red aligns its fourth token at the earliest
+    assert_eq!(stats[6].red_wins + stats[6].yellow_wins, 0);
+    assert!(stats[7].red_wins > 0);
+}
+
+#[test]
+fn test_unique_positions() {
+    assert_eq!(Perft::new().unique_positions(8, false), UNIQUE_POSITION_COUNTS[..=8]);
+    // a symmetric position is its own mirror
+    assert_eq!(Perft::new().unique_positions(3, true), [1, 4, 25, 121]);
+    assert!(Perft::new().unique_positions(-1, false).is_empty());
+
+    let mut perft = Perft::new();
+    perft.game = Game::with_dimensions(Dimensions::new(4, 4).unwrap());
+    let counts = perft.unique_positions(6, true);
+    assert!(counts.iter().zip(perft.unique_positions(6, false)).all(|(&mirrors, all)| all.div_ceil(2) <= mirrors && mirrors <= all));
+}
+
+#[test]
+fn test_write_unique_positions() {
+    let mut output = Vec::new();
+    let counts = Perft::new().write_unique_positions(2, false, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert_eq!(output.lines().count() as u64, counts.iter().sum::<u64>());
+    assert_eq!(output.lines().next(), Some("0 7/7/7/7/7/7 R"));
+    assert_eq!(output.lines().filter(|line| line.starts_with("2 ")).count(), 49);
+    assert!(output.contains("2 7/7/7/7/3Y3/3R3 R"));
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use puissance_4::api::{engine::*, record::GameRecord, rules::PopOut, search::{Search, WIN_SCORE}};
+use rstest::rstest;
+
+fn popout_game(history: &str) -> Game {
+    let mut game = Game::with_rules(PopOut, Dimensions::default());
+    game.make_push_bulk(history);
+    game
+}
+
+#[test]
+fn test_pop_shifts_the_column_down() {
+    let game = popout_game("14p1");
+    assert_eq!(game.to_position(), "7/7/7/7/7/3Y3 Y PopOut");
+    assert_eq!(game.get_possible_moves(), vec![6, 5, 4, 3, 2, 1, 0, 10]);
+
+    let other = popout_game("24p2");
+    assert_eq!(game.zobrist_key, other.zobrist_key);
+    assert_eq!(game.mirrored().zobrist_key, popout_game("74p7").zobrist_key);
+    assert_eq!(game.canonical_key(), popout_game("74p7").canonical_key());
+
+    let mut game = popout_game("4414p4");
+    assert_eq!(game.to_position(), "7/7/7/7/3Y3/R2Y3 Y PopOut");
+    game.unmake_move();
+    assert_eq!(game.zobrist_key, popout_game("4414").zobrist_key);
+    assert_eq!(game.to_position(), popout_game("4414").to_position());
+}
+
+#[rstest]
+#[case(false, "1", Move::pop(0), MoveError::InvalidPop(0))] // not a PopOut game
+#[case(true, "14", Move::pop(3), MoveError::InvalidPop(3))] // token of the opponent
+#[case(true, "14", Move::pop(1), MoveError::InvalidPop(1))] // empty column
+#[case(true, "14", Move::pop(7), MoveError::OutOfRange(7))]
+fn test_invalid_pops(#[case] popout: bool, #[case] history: &str, #[case] mv: Move, #[case] expected: MoveError) {
+    let mut game = Game::new();
+    if popout {
+        game.rules = Arc::new(PopOut);
+    }
+    game.make_push_bulk(history);
+    assert_eq!(game.try_play(mv), Err(expected));
+}
+
+#[rstest]
+#[case("7/7/7/R6/YRRR3/RYYY2Y R PopOut", GameOutcome::Win(Color::Red))] // both players align, the player who popped wins
+#[case("7/7/7/7/Y6/RYYYRRR R PopOut", GameOutcome::Win(Color::Yellow))]
+fn test_pop_outcome(#[case] position: &str, #[case] expected: GameOutcome) {
+    let mut game = Game::from_position(position).unwrap();
+    game.try_play(Move::pop(0)).unwrap();
+    assert_eq!(game.outcome, expected);
+
+    game.unmake_move();
+    assert_eq!(game.to_position(), position);
+    assert_eq!(game.outcome, GameOutcome::Ongoing);
+}
+
+#[test]
+fn test_full_board_is_not_a_draw() {
+    let game = popout_game("331317422446336465157624214762167173725555");
+    assert_eq!(game.outcome, GameOutcome::Ongoing);
+    assert!(game.get_possible_moves().iter().all(|&m| m >= WIDTH));
+}
+
+#[test]
+fn test_search_plays_winning_pop() {
+    let mut game = Game::from_position("7/7/7/R6/YRRR3/RYYY2Y R").unwrap();
+    game.rules = Arc::new(PopOut);
+
+    let result = Search::new(4).think(&mut game);
+    assert_eq!(result.best_move, Some(Move::pop(0)));
+    assert_eq!(result.score, WIN_SCORE - 11);
+}
+
+#[rstest]
+#[case(4, 4, 4, 8, 288152)]
+#[case(5, 4, 3, 6, 32289)]
+fn test_perft_popout(#[case] width: i32, #[case] height: i32, #[case] connect: i32, #[case] depth: i32, #[case] expected: u64) {
+    let mut perft = Perft::new();
+    perft.game = Game::with_rules(PopOut, Dimensions::new(width, height).unwrap().with_connect(connect).unwrap());
+    assert_eq!(perft.run(depth), expected);
+}
+
+#[test]
+fn test_pop_notation() {
+    assert_eq!("p4".parse::<Move>(), Ok(Move::pop(3)));
+    assert_eq!(Move::pop(3).to_string(), "p4");
+    assert_eq!(Move::parse_sequence("4p4"), Ok(vec![Move::from_col(3), Move::pop(3)]));
+    assert!(Move::parse_sequence("4pp4").is_err());
+    assert!(Move::parse_sequence("4p").is_err());
+
+    let game = popout_game("4414p4");
+    let record = GameRecord::from_game(&game, "Red", "Yellow");
+    let text = record.to_string();
+    assert!(text.contains("[Variant \"PopOut\"]"));
+    assert!(text.contains("3. p4"));
+    assert_eq!(text.parse::<GameRecord>().unwrap().replay().unwrap().zobrist_key, game.zobrist_key);
+}
@@ -0,0 +1,126 @@
+use puissance_4::api::{engine::{Color, Dimensions, Game, GameOutcome}, position::PositionError, rules::{rules_from_name, PopTen}};
+use rstest::rstest;
+
+#[rstest]
+#[case("")]
+#[case("4")]
+#[case("4455667")]
+#[case("56552543455334444321331661717")]
+#[case("331317422446336465157624214762167173725555")]
+fn test_position_round_trip(#[case] history: &str) {
+    let mut game = Game::new();
+    game.make_push_bulk(history);
+
+    let loaded = Game::from_position(&game.to_position()).unwrap();
+    assert_eq!(loaded.to_position(), game.to_position());
+    assert_eq!(loaded.zobrist_key, game.zobrist_key);
+    assert_eq!(loaded.outcome, game.outcome);
+    assert_eq!(loaded.turn_color, game.turn_color);
+    assert_eq!(loaded.get_possible_moves(), game.get_possible_moves());
+}
+
+#[test]
+fn test_position_notation() {
+    let mut game = Game::new();
+    assert_eq!(game.to_position(), "7/7/7/7/7/7 R");
+
+    game.make_push_bulk("4451");
+    assert_eq!(game.to_position(), "7/7/7/7/3Y3/Y2RR2 R");
+    assert_eq!("7/7/7/7/3Y3/Y2RR2 R".parse::<Game>().unwrap().tokens(Color::Red), game.tokens(Color::Red));
+}
+
+#[test]
+fn test_loaded_position_can_be_played() {
+    let mut game = Game::from_position("7/7/7/7/7/YYY1RRR R").unwrap();
+    game.make_push(3);
+    assert_eq!(game.outcome, GameOutcome::Win(Color::Red));
+}
+
+#[rstest]
+#[case("7/7/7/7/7/7/7/7/7/7 R", PositionError::InvalidSize)] // 70 squares
+#[case("/ R", PositionError::InvalidSize)]
+#[case("7/7/7/7/7/7", PositionError::InvalidFormat)]
+#[case("7/7/7/7/7/7 X", PositionError::InvalidFormat)]
+#[case("7/7/7/7/7/R7 Y", PositionError::InvalidRow(5))]
+#[case("7/7/7/7/7/6 R", PositionError::InvalidRow(5))]
+#[case("7/7/7/7/R6/7 Y", PositionError::FloatingToken(0))]
+#[case("7/7/7/7/7/RR5 Y", PositionError::InvalidTokenCount)]
+#[case("7/7/7/7/7/R6 R", PositionError::WrongSideToMove)]
+#[case("7/Y6/R6/R6/R6/RYYY3 R", PositionError::Unreachable)] // red won before the last yellow token
+#[case("7/7/7/7/7/YYYYRRRR R", PositionError::InvalidRow(5))]
+#[case("7/7/7/7/YYYYRRR/RRRRYYY R", PositionError::Unreachable)] // two winners
+#[case("7/7/7/7/7/7 R 1", PositionError::InvalidConnect)]
+#[case("7/7/7/7/7/7 R 8", PositionError::InvalidConnect)] // longer than the board
+#[case("7/7/7/7/7/7 R 100000", PositionError::InvalidConnect)]
+#[case("7/7/7/7/7/7 R Connect6", PositionError::UnknownVariant)]
+#[case("7/7/7/7/7/7 R PopOut 3", PositionError::InvalidFormat)] // the connect count comes first
+#[case("7/7/7/7/7/7 R 5 FiveInARow", PositionError::InvalidSize)]
+#[case("9/9/9/9/9/9 R FiveInARow", PositionError::InvalidConnect)]
+#[case("9/9/9/9/9/9 R 5 FiveInARow", PositionError::Unreachable)] // the setup tokens are missing
+#[case("7/7/7/7/7/3Y3 Y PopOut", PositionError::InvalidTokenCount)] // only reached by a pop
+#[case("7/7/7/7/7/7 R PopOut filled", PositionError::InvalidFormat)] // the board is never filled first
+#[case("4/4/4/4 R 3 PopTen filled", PositionError::Unreachable)]
+fn test_position_errors(#[case] position: &str, #[case] expected: PositionError) {
+    assert_eq!(Game::from_position(position).err(), Some(expected));
+}
+
+#[test]
+fn test_position_other_size() {
+    let game: Game = "8/8/8/8/8/8/2RY4 R".parse().unwrap();
+    assert_eq!(game.dims(), Dimensions::new(8, 7).unwrap());
+    assert_eq!(game.get_possible_moves(), vec![7, 6, 5, 4, 3, 2, 1, 0]);
+    assert_eq!(game.to_position(), "8/8/8/8/8/8/2RY4 R");
+}
+
+#[test]
+fn test_position_connect() {
+    let mut game = Game::with_dimensions(Dimensions::default().with_connect(3).unwrap());
+    game.make_push_bulk("1122");
+    assert_eq!(game.to_position(), "7/7/7/7/YY5/RR5 R 3");
+
+    let mut loaded = Game::from_position(&game.to_position()).unwrap();
+    assert_eq!(loaded.dims(), game.dims());
+    assert_eq!(loaded.zobrist_key, game.zobrist_key);
+    loaded.make_push(2);
+    assert_eq!(loaded.outcome, GameOutcome::Win(Color::Red));
+}
+
+#[rstest]
+#[case("Standard", 3, "1122")]
+#[case("PopOut", 4, "4455")]
+#[case("PopTen", 4, "1213141")] // aligned while filling the board
+#[case("FiveInARow", 5, "2233")]
+fn test_position_variants(#[case] variant: &str, #[case] connect: i32, #[case] history: &str) {
+    let dims = Dimensions::default().with_connect(connect).unwrap();
+    let mut game = Game::with_shared_rules(rules_from_name(variant).unwrap(), dims);
+    game.make_push_bulk(history);
+
+    let position = game.to_position();
+    assert_eq!(position.contains(&format!(" {}", connect)), connect != 4, "{}", position);
+    assert_eq!(position.ends_with(variant), variant != "Standard", "{}", position);
+
+    let loaded = Game::from_position(&position).unwrap();
+    assert_eq!(loaded.to_position(), position);
+    assert_eq!(loaded.dims(), game.dims());
+    assert_eq!(loaded.rules.name(), variant);
+    assert_eq!(loaded.setup_len, game.setup_len);
+    assert_eq!(loaded.zobrist_key, game.zobrist_key);
+    assert_eq!(loaded.outcome, game.outcome);
+    assert_eq!(loaded.get_possible_moves(), game.get_possible_moves());
+}
+
+#[test]
+fn test_position_filled_board() {
+    let mut game = Game::with_rules(PopTen, Dimensions::new(4, 4).unwrap().with_connect(3).unwrap());
+    while !game.board_filled() {
+        game.make_move(*game.get_possible_moves().last().unwrap());
+    }
+    let position = game.to_position();
+    assert!(position.ends_with(" 3 PopTen filled"), "{}", position);
+
+    let loaded = Game::from_position(&position).unwrap();
+    assert_eq!(loaded.zobrist_key, game.zobrist_key);
+    assert_eq!(loaded.get_possible_moves(), game.get_possible_moves());
+    // a full board is never in the filling phase
+    assert_eq!(Game::from_position(position.trim_end_matches(" filled")).err(), Some(PositionError::Unreachable));
+}
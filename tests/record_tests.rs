@@ -0,0 +1,87 @@
+use puissance_4::api::{engine::{Color, Dimensions, Game, GameOutcome, MoveError}, record::{GameRecord, RecordError, RecordedMove}};
+
+const RECORD: &str = "[Red \"Human\"]
+[Yellow \"Negamax\"]
+[YellowDepth \"8\"]
+[Date \"2025.01.31\"]
+[Result \"1-0\"]
+
+1. 4 4 {[%eval 12] center reply} 2. 5 5 {[%eval -5]} 3. 6 6 {blocks nothing} 4. 7 1-0
+";
+
+#[test]
+fn test_record_round_trip() {
+    let record: GameRecord = RECORD.parse().unwrap();
+    assert_eq!(record.red, "Human");
+    assert_eq!(record.yellow_depth, Some(8));
+    assert_eq!(record.red_depth, None);
+    assert_eq!(record.date.as_deref(), Some("2025.01.31"));
+    assert_eq!(record.result, GameOutcome::Win(Color::Red));
+    assert_eq!(record.moves.len(), 7);
+    assert_eq!(record.moves[1].score, Some(12));
+    assert_eq!(record.moves[1].comment.as_deref(), Some("center reply"));
+    assert_eq!(record.moves[5].comment.as_deref(), Some("blocks nothing"));
+
+    assert_eq!(record.to_string(), RECORD);
+}
+
+#[test]
+fn test_record_replay() {
+    let record: GameRecord = RECORD.parse().unwrap();
+    assert_eq!(record.replay().unwrap().outcome, record.result);
+
+    let game = record.replay_to(4).unwrap();
+    assert_eq!(game.board.history.len(), 4);
+    let columns: Vec<RecordedMove> = record.moves[..4].iter().map(|m| RecordedMove::new(m.mv)).collect();
+    assert_eq!(GameRecord::from_game(&game, "Human", "Negamax").moves, columns);
+}
+
+#[test]
+fn test_record_file() {
+    let record: GameRecord = RECORD.parse().unwrap();
+    let path = std::env::temp_dir().join(format!("puissance_4_record_{}.p4n", std::process::id()));
+
+    record.write_to_file(&path).unwrap();
+    let loaded = GameRecord::read_from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, record);
+}
+
+#[test]
+fn test_record_errors() {
+    assert!(matches!("[Red Human]\n".parse::<GameRecord>(), Err(RecordError::InvalidTag(_))));
+    assert!(matches!("1. 3 x".parse::<GameRecord>(), Err(RecordError::InvalidMove(_))));
+    assert!(matches!("1. 3 {[%eval high]}".parse::<GameRecord>(), Err(RecordError::InvalidScore(_))));
+    assert!(matches!("1. 3 {unclosed".parse::<GameRecord>(), Err(RecordError::UnclosedComment)));
+    assert!(matches!("1. 9".parse::<GameRecord>().unwrap().replay(), Err(RecordError::IllegalMove(0, MoveError::OutOfRange(8)))));
+    assert!(matches!("1. 0".parse::<GameRecord>(), Err(RecordError::InvalidMove(_))));
+    assert!(matches!(GameRecord::read_from_file("does/not/exist.p4n"), Err(RecordError::Io(_))));
+    assert!(matches!("[Size \"1x2147483647\"]".parse::<GameRecord>(), Err(RecordError::InvalidTag(_))));
+}
+
+#[test]
+fn test_record_other_size() {
+    let mut game = Game::with_dimensions(Dimensions::new(8, 7).unwrap());
+    game.make_push_bulk("5566778");
+    let record = GameRecord::from_game(&game, "Red", "Yellow");
+
+    let text = record.to_string();
+    assert!(text.contains("[Size \"8x7\"]"));
+    let loaded: GameRecord = text.parse().unwrap();
+    assert_eq!(loaded.replay().unwrap().outcome, GameOutcome::Win(Color::Red));
+}
+
+#[test]
+fn test_record_connect_tag() {
+    let mut game = Game::with_dimensions(Dimensions::default().with_connect(3).unwrap());
+    game.make_push_bulk("11223");
+    let text = GameRecord::from_game(&game, "Red", "Yellow").to_string();
+    assert!(text.contains("[Connect \"3\"]"));
+    assert!(!text.contains("[Size"));
+
+    let loaded: GameRecord = text.parse().unwrap();
+    assert_eq!(loaded.replay().unwrap().outcome, GameOutcome::Win(Color::Red));
+
+    assert!(matches!("[Connect \"8\"]".parse::<GameRecord>(), Err(RecordError::InvalidTag(_))));
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use puissance_4::api::{engine::*, record::GameRecord, rules::{rules_from_name, FiveInARow, PopTen, POP_TEN_TARGET}, search::{Search, MAX_PLY, WIN_SCORE}};
+use rstest::rstest;
+
+mod common;
+use common::play_random_move;
+
+// fills the board of a Pop Ten game, red aligning four tokens in the first column
+fn filled_pop_ten() -> Game {
+    let mut game = Game::with_rules(PopTen, Dimensions::default());
+    game.make_push_bulk("1213141");
+    while game.board.bitboard != game.dims().full() {
+        assert_eq!(game.outcome, GameOutcome::Ongoing);
+        game.make_move(*game.get_possible_moves().last().unwrap());
+    }
+    game
+}
+
+#[test]
+fn test_pop_ten_fill_phase_ignores_alignments() {
+    let mut game = Game::with_rules(PopTen, Dimensions::default());
+    game.make_push_bulk("1213141");
+    assert_eq!(game.outcome, GameOutcome::Ongoing);
+    assert_eq!(game.try_play(Move::pop(1)), Err(MoveError::InvalidPop(1)));
+
+    let game = filled_pop_ten();
+    assert_eq!(game.outcome, GameOutcome::Ongoing);
+    assert!(game.get_possible_moves().iter().all(|&m| m >= WIDTH));
+    assert_eq!(game.clone().try_play(Move::from_col(0)), Err(MoveError::ColumnFull(0)));
+}
+
+#[test]
+fn test_pop_ten_collects_aligned_tokens() {
+    let mut game = filled_pop_ten();
+    let zobrist_key = game.zobrist_key;
+    let position = game.to_position();
+
+    game.try_play(Move::pop(0)).unwrap();
+    assert_eq!(PopTen::collected(&game, Color::Red), 1);
+    assert_eq!(game.height(0), HEIGHT - 1);
+    // pushes are only allowed while filling the board
+    assert_eq!(game.try_play(Move::from_col(0)), Err(MoveError::InvalidPush(0)));
+
+    game.unmake_move();
+    assert_eq!(game.zobrist_key, zobrist_key);
+    assert_eq!(game.to_position(), position);
+}
+
+#[test]
+fn test_pop_ten_returns_other_tokens() {
+    let mut game = filled_pop_ten();
+    // red pops a token outside of any alignment, it goes back on top of its column
+    let aligned = game.aligned_squares(game.tokens(Color::Red));
+    let col = (0..WIDTH).find(|&col| game.rules.can_pop(&game, col) && aligned & game.dims().bit(col, 0) == 0).unwrap();
+    let zobrist_key = game.zobrist_key;
+    let position = game.to_position();
+
+    game.try_play(Move::pop(col)).unwrap();
+    assert_eq!(PopTen::collected(&game, Color::Red), 0);
+    assert_eq!(game.height(col), HEIGHT);
+    assert_eq!(game.board.color_bitboard & game.dims().bit(col, HEIGHT - 1), 0); // red token, yellow to move
+    assert_eq!(game.zobrist_key, game.mirrored().mirrored().zobrist_key);
+
+    game.unmake_move();
+    assert_eq!(game.zobrist_key, zobrist_key);
+    assert_eq!(game.to_position(), position);
+}
+
+#[rstest]
+#[case(1)]
+#[case(2)]
+#[case(3)]
+fn test_pop_ten_playout_ends(#[case] seed: u64) {
+    let mut game = Game::with_rules(PopTen, Dimensions::default());
+    let mut state = seed;
+    while !game.outcome.is_over() {
+        play_random_move(&mut game, &mut state);
+    }
+
+    match game.outcome {
+        GameOutcome::Win(color) => {
+            assert_eq!(PopTen::collected(&game, color), POP_TEN_TARGET);
+            assert!(PopTen::collected(&game, color.toggle()) < POP_TEN_TARGET);
+        }
+        _ => assert!(game.get_possible_moves().is_empty())
+    }
+
+    while !game.board.history.is_empty() {
+        game.unmake_move();
+    }
+    assert_eq!(game.zobrist_key, 0);
+    assert_eq!(game.board.bitboard, 0);
+}
+
+#[test]
+fn test_pop_ten_keys_tell_positions_apart() {
+    // returned pops keep the token count, so the same tokens come back with either side to move,
+    // and collected ones can leave tokens met while filling the board
+    let dims = Dimensions::new(4, 4).unwrap().with_connect(3).unwrap();
+    let mut seen = HashMap::new();
+    let (mut both_sides, mut both_phases) = (0, 0);
+    let mut state = 0x9E37_79B9_7F4A_7C15;
+    for _ in 0..200 {
+        let mut game = Game::with_rules(PopTen, dims);
+        while !game.outcome.is_over() && game.board.history.len() < 200 {
+            play_random_move(&mut game, &mut state);
+
+            let (red_to_move, filled) = (game.turn_color == Color::Red, game.board_filled());
+            let tokens = (game.board.bitboard, game.tokens(Color::Red));
+            let keys = (game.zobrist_key, game.position_key(), game.to_position());
+            assert_eq!(*seen.entry((tokens, red_to_move, filled)).or_insert(keys.clone()), keys);
+            for (other_side, other_phase) in [(!red_to_move, filled), (red_to_move, !filled), (!red_to_move, !filled)] {
+                if let Some(other) = seen.get(&(tokens, other_side, other_phase)) {
+                    assert_ne!(other.0, keys.0, "{}", keys.2);
+                    assert_ne!(other.1, keys.1, "{}", keys.2);
+                    assert_ne!(other.2, keys.2);
+                    both_sides += usize::from(other_side != red_to_move);
+                    both_phases += usize::from(other_phase != filled);
+                }
+            }
+        }
+    }
+    assert!(both_sides > 0 && both_phases > 0, "{} {}", both_sides, both_phases);
+}
+
+#[test]
+fn test_perft_pop_ten() {
+    let mut perft = Perft::new();
+    perft.game = Game::with_rules(PopTen, Dimensions::new(3, 2).unwrap().with_connect(2).unwrap());
+    let fill = perft.run(6);
+    assert!(perft.run(9) > fill);
+}
+
+#[test]
+fn test_perft_pop_ten_tt_across_the_fill() {
+    // the same tokens can be met while filling the board and after, with other moves allowed
+    let mut perft = Perft::new();
+    perft.game = Game::with_rules(PopTen, Dimensions::new(3, 2).unwrap().with_connect(2).unwrap());
+    for depth in 0..=12 {
+        assert_eq!(perft.run_tt(depth), perft.run(depth), "depth {}", depth);
+    }
+}
+
+#[test]
+fn test_five_in_a_row_setup() {
+    let game = Game::with_rules(FiveInARow, Dimensions::default());
+    assert_eq!(game.to_position(), "Y7R/R7Y/Y7R/R7Y/Y7R/R7Y R 5 FiveInARow");
+    assert_eq!(game.setup_len, 12);
+    assert_eq!(game.get_possible_moves(), vec![7, 6, 5, 4, 3, 2, 1]);
+
+    let mut perft = Perft::new();
+    perft.game = game;
+    assert_eq!(perft.run(2), 49);
+    perft.reset();
+    assert_eq!(perft.game.to_position(), "Y7R/R7Y/Y7R/R7Y/Y7R/R7Y R 5 FiveInARow");
+}
+
+#[rstest]
+#[case("223344", GameOutcome::Ongoing)] // four in a row with the first column
+#[case("2233445", GameOutcome::Win(Color::Red))]
+fn test_five_in_a_row_outcome(#[case] history: &str, #[case] expected: GameOutcome) {
+    let mut game = Game::with_rules(FiveInARow, Dimensions::default());
+    game.make_push_bulk(history);
+    assert_eq!(game.outcome, expected);
+
+    let record = GameRecord::from_game(&game, "Red", "Yellow");
+    assert_eq!(record.moves.len(), history.len());
+    assert!(record.to_string().contains("[Variant \"FiveInARow\"]"));
+    assert_eq!(record.to_string().parse::<GameRecord>().unwrap().replay().unwrap().zobrist_key, game.zobrist_key);
+}
+
+#[test]
+fn test_search_five_in_a_row() {
+    let mut game = Game::with_rules(FiveInARow, Dimensions::default());
+    game.make_push_bulk("223344");
+    let result = Search::new(4).think(&mut game);
+    assert_eq!(result.best_move, Some(Move::from_col(4)));
+}
+
+#[test]
+fn test_search_pop_ten_alignments_while_filling() {
+    // both players have aligned four tokens, which doesn't end the game while filling the board
+    let mut game = Game::from_position("YY1RYYY/YRRYYRY/RRRRRYY/YRRRRYR/YYRYRRY/RRYYRYR Y PopTen").unwrap();
+    let result = Search::new(1).think(&mut game);
+    assert!(result.score.abs() < WIN_SCORE - MAX_PLY, "{}", result.score);
+    assert_eq!(result.value, None);
+    assert_eq!(result.best_move, Some(Move::from_col(2)));
+}
+
+#[rstest]
+#[case("Standard")]
+#[case("PopOut")]
+#[case("PopTen")]
+#[case("FiveInARow")]
+fn test_rules_from_name(#[case] name: &str) {
+    assert_eq!(rules_from_name(name).unwrap().name(), name);
+    assert!(rules_from_name("Connect6").is_none());
+}
@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+use puissance_4::api::{engine::Game, search::{GameValue, Search, WIN_SCORE}};
+use rstest::rstest;
+
+#[test]
+fn test_think_time_limit() {
+    let mut game = Game::new();
+    let mut search = Search::new(42).with_time_limit(Duration::from_millis(200));
+
+    let start = Instant::now();
+    let result = search.think(&mut game);
+
+    assert!(result.best_move.is_some());
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn test_think_node_limit() {
+    let mut game = Game::new();
+    game.make_push_bulk("4434");
+    let mut search = Search::new(42).with_node_limit(10_000);
+
+    assert!(search.think(&mut game).best_move.is_some());
+    assert!(search.nodes() < 11_000);
+}
+
+#[test]
+fn test_think_plays_immediate_win() {
+    let mut game = Game::new();
+    game.make_push_bulk("445566");
+    let mut search = Search::new(6).with_time_limit(Duration::from_secs(5));
+
+    let result = search.think(&mut game);
+    assert!(result.best_move == Some("3".parse().unwrap()) || result.best_move == Some("7".parse().unwrap()));
+    assert_eq!(result.pv.first().copied(), result.best_move);
+    assert_eq!(result.root_scores.len(), 7);
+    assert!(result.root_scores.iter().all(|&(_, score)| score <= result.score));
+    assert_eq!(result.depth, 6);
+}
+
+#[test]
+fn test_stop_handle_aborts_think() {
+    let mut game = Game::new();
+    let mut search = Search::new(42);
+    let stop = search.stop_handle();
+
+    let start = Instant::now();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        stop.stop();
+    });
+    search.think(&mut game);
+
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn test_ponder_warms_transposition_table() {
+    let game = Game::new();
+    let ponder = Search::new(42).with_time_limit(Duration::from_millis(50)).ponder(&game);
+    std::thread::sleep(Duration::from_millis(100));
+
+    let search = ponder.stop();
+    assert!(!search.tt.is_empty());
+    assert_eq!(search.time_limit, Some(Duration::from_millis(50)));
+    assert!(!search.stop_handle().is_stopped());
+}
+
+#[rstest]
+#[case("445566", GameValue::Win(1))]
+#[case("44556", GameValue::Loss(1))]
+#[case("3447674774764676664232", GameValue::Win(8))]
+fn test_think_finds_mate_distance(#[case] history: &str, #[case] expected: GameValue) {
+    let mut game = Game::new();
+    game.make_push_bulk(history);
+    let mut search = Search::new(16);
+
+    assert_eq!(search.think(&mut game).value, Some(expected));
+}
+
+#[test]
+fn test_win_score_counts_tokens() {
+    let mut game = Game::new();
+    game.make_push_bulk("445566");
+    let mut search = Search::new(8);
+
+    // winning with the 7th token of the game
+    assert_eq!(search.think(&mut game).score, WIN_SCORE - 7);
+}
+
+#[test]
+fn test_mirrored_positions_share_tt_entries() {
+    let mut game = Game::new();
+    game.make_push_bulk("1");
+    let mut search = Search::new(8);
+    let result = search.think(&mut game);
+
+    // the reply to the mirrored move was stored by the search on the other side
+    let mut mirror = Game::new();
+    mirror.make_push_bulk("74");
+    let entry = search.tt.probe(mirror.canonical_key()).unwrap();
+    assert_eq!(entry.depth, 8);
+
+    let mut mirror = Game::new();
+    mirror.make_push_bulk("7");
+    let mirror_result = Search::new(8).think(&mut mirror);
+    assert_eq!(mirror_result.score, result.score);
+    assert_eq!(mirror_result.best_move.map(|mv| mv.mirrored(7)), result.best_move);
+}
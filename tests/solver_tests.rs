@@ -0,0 +1,83 @@
+use puissance_4::api::{engine::{Color, Dimensions, Game, GameOutcome, Move}, search::{GameValue, Solver}};
+use rstest::rstest;
+
+/// Plain minimax over every continuation, only usable on nearly full boards.
+fn reference_score(game: &mut Game) -> i32 {
+    let ply = game.board.history.len() as i32;
+    if game.winner().is_some() {
+        return -(44 - ply) / 2;
+    }
+
+    let moves = game.get_possible_moves();
+    if moves.is_empty() {
+        return 0;
+    }
+
+    let mut best = i32::MIN;
+    for m in moves {
+        game.make_push(m);
+        best = best.max(-reference_score(game));
+        game.unmake_push();
+    }
+    best
+}
+
+#[rstest]
+#[case("34476747747646766642321223332251")]
+#[case("56552543455334444321331661717")]
+#[case("4444655565455334177777722222")]
+#[case("331317422446336465157624214762167173")]
+fn test_solver_matches_minimax(#[case] history: &str) {
+    let mut game = Game::new();
+    game.make_push_bulk(history);
+
+    let mut solver = Solver::new();
+    assert_eq!(solver.score(&mut game), reference_score(&mut game));
+}
+
+#[rstest]
+#[case("445566", GameValue::Win(1))]
+#[case("44556", GameValue::Loss(1))]
+#[case("4455667", GameValue::Loss(0))]
+#[case("3447674774764676664232", GameValue::Win(8))]
+#[case("5655254345533444432133", GameValue::Draw)]
+fn test_solver_value(#[case] history: &str, #[case] expected: GameValue) {
+    let mut game = Game::new();
+    game.make_push_bulk(history);
+
+    let mut solver = Solver::new();
+    assert_eq!(solver.solve(&mut game), expected);
+}
+
+#[rstest]
+#[case(4, 4, GameValue::Draw)]
+#[case(5, 4, GameValue::Draw)]
+fn test_solver_small_boards(#[case] width: i32, #[case] height: i32, #[case] expected: GameValue) {
+    let mut game = Game::with_dimensions(Dimensions::new(width, height).unwrap());
+
+    let mut solver = Solver::new();
+    assert_eq!(solver.solve(&mut game), expected);
+}
+
+#[rstest]
+#[case(4, 3, 2)]
+#[case(4, 4, 4)]
+#[case(5, 4, 6)]
+fn test_solver_connect_3(#[case] width: i32, #[case] height: i32, #[case] expected: i32) {
+    let mut game = Game::with_dimensions(Dimensions::new(width, height).unwrap().with_connect(3).unwrap());
+
+    let mut solver = Solver::new();
+    assert_eq!(solver.score(&mut game), expected);
+}
+
+#[test]
+#[should_panic(expected = "PopOut")]
+fn test_solver_rejects_pops() {
+    let mut game = Game::from_position("7/7/7/R6/YRRR3/RYYY2Y R PopOut").unwrap();
+    // red wins right away by popping the first column, a move the solver would miss
+    let mut popped = game.clone();
+    popped.try_play(Move::pop(0)).unwrap();
+    assert_eq!(popped.outcome, GameOutcome::Win(Color::Red));
+
+    Solver::new().solve(&mut game);
+}
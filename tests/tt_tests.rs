@@ -0,0 +1,51 @@
+use puissance_4::api::tt::{NodeType, TTEntry, TranspositionTable};
+
+fn entry(flag: NodeType, depth: i32, value: i32) -> TTEntry {
+    TTEntry { flag, depth, value, best_move: None }
+}
+
+#[test]
+fn test_store_and_probe() {
+    let tt = TranspositionTable::new(1);
+    tt.store(42, TTEntry { best_move: Some(6), ..entry(NodeType::LowerBound, 7, -1234) });
+
+    let stored = tt.probe(42).unwrap();
+    assert_eq!(stored.flag, NodeType::LowerBound);
+    assert_eq!(stored.depth, 7);
+    assert_eq!(stored.value, -1234);
+    assert_eq!(stored.best_move, Some(6));
+    assert!(tt.probe(43).is_none());
+    assert_eq!(tt.len(), 1);
+}
+
+#[test]
+fn test_depth_preferred_replacement() {
+    let tt = TranspositionTable::new(1);
+    let capacity = tt.capacity() as u64 / 2;
+
+    // three keys sharing the same bucket
+    tt.store(1, entry(NodeType::Exact, 10, 1));
+    tt.store(1 + capacity, entry(NodeType::Exact, 2, 2));
+    tt.store(1 + 2 * capacity, entry(NodeType::Exact, 3, 3));
+
+    assert_eq!(tt.probe(1).unwrap().value, 1);
+    assert!(tt.probe(1 + capacity).is_none());
+    assert_eq!(tt.probe(1 + 2 * capacity).unwrap().value, 3);
+
+    let stats = tt.stats();
+    assert_eq!(stats.stores, 3);
+    assert_eq!(stats.collisions, 1);
+    assert_eq!(stats.probes, 3);
+    assert_eq!(stats.hits, 2);
+}
+
+#[test]
+fn test_clear() {
+    let tt = TranspositionTable::new(1);
+    tt.store(5, entry(NodeType::UpperBound, 1, 0));
+    tt.clear();
+
+    assert!(tt.is_empty());
+    assert!(tt.probe(5).is_none());
+    assert_eq!(tt.stats().stores, 0);
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use puissance_4::api::{engine::*, rules::PopOut};
+use rstest::rstest;
+
+mod common;
+use common::{play_random_move, xorshift};
+
+/// Slow win checker testing every window of `connect` squares one square at a time, without any bitboard trick.
+struct Reference {
+    windows: Vec<Vec<Bitboard>> // every window of `connect` squares inside the board
+}
+
+impl Reference {
+    fn new(dims: Dimensions) -> Self {
+        let mut windows = Vec::new();
+        for col in 0..dims.width {
+            for row in 0..dims.height {
+                for (dx, dy) in DIRECTIONS {
+                    let squares: Vec<(i32, i32)> = (0..dims.connect).map(|k| (col + k * dx, row + k * dy)).collect();
+                    if squares.iter().all(|&(c, r)| (0..dims.width).contains(&c) && (0..dims.height).contains(&r)) {
+                        windows.push(squares.iter().map(|&(c, r)| dims.bit(c, r)).collect());
+                    }
+                }
+            }
+        }
+        Self { windows }
+    }
+
+    /// Squares of the tokens belonging to an alignment.
+    fn aligned(&self, tokens: Bitboard) -> Bitboard {
+        self.windows.iter()
+            .filter(|window| window.iter().all(|&bit| tokens & bit != 0))
+            .fold(0, |aligned, window| aligned | window.iter().fold(0, |mask, &bit| mask | bit))
+    }
+
+    /// Empty squares completing an alignment of the tokens.
+    fn winning(&self, tokens: Bitboard, occupied: Bitboard) -> Bitboard {
+        let mut winning = 0;
+        for window in &self.windows {
+            let missing: Vec<Bitboard> = window.iter().copied().filter(|&bit| tokens & bit == 0).collect();
+            if let [bit] = missing[..] && occupied & bit == 0 {
+                winning |= bit;
+            }
+        }
+        winning
+    }
+
+    /// Color of the winner following [Game::check_win]: the player who just moved, or the opponent after a pop.
+    fn winner(&self, game: &Game) -> Option<Color> {
+        let &PlayedMove { mv: last_move, color, .. } = game.board.history.last()?;
+        if self.aligned(game.tokens(color)) != 0 {
+            return Some(color);
+        }
+        if last_move.is_pop() && self.aligned(game.tokens(color.toggle())) != 0 {
+            return Some(color.toggle());
+        }
+        None
+    }
+
+    fn check(&self, game: &Game) {
+        assert_eq!(game.check_win(), self.winner(game), "{}", game.to_position());
+        for color in [Color::Red, Color::Yellow] {
+            let tokens = game.tokens(color);
+            assert_eq!(game.aligned_squares(tokens), self.aligned(tokens), "{}", game.to_position());
+            assert_eq!(game.winning_squares(color), self.winning(tokens, game.board.bitboard), "{}", game.to_position());
+        }
+    }
+}
+
+fn walk(game: &mut Game, reference: &Reference, depth: i32) -> u64 {
+    reference.check(game);
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 1;
+    for mv in game.get_possible_moves() {
+        game.make_move(mv);
+        nodes += walk(game, reference, depth - 1);
+        game.unmake_move();
+    }
+    nodes
+}
+
+fn random_bitboard(state: &mut u64) -> Bitboard {
+    (xorshift(state) as Bitboard) << 64 | xorshift(state) as Bitboard
+}
+
+#[rstest]
+#[case(7, 6, 4, 5)]
+#[case(4, 4, 3, 8)]
+#[case(5, 4, 4, 8)]
+#[case(8, 7, 4, 4)]
+#[case(9, 6, 5, 4)]
+#[case(8, 8, 4, 4)]
+fn test_every_position_matches_reference(#[case] width: i32, #[case] height: i32, #[case] connect: i32, #[case] depth: i32) {
+    let dims = Dimensions::new(width, height).unwrap().with_connect(connect).unwrap();
+    assert!(walk(&mut Game::with_dimensions(dims), &Reference::new(dims), depth) > 1);
+}
+
+#[test]
+fn test_every_popout_position_matches_reference() {
+    let dims = Dimensions::new(4, 4).unwrap();
+    walk(&mut Game::with_rules(PopOut, dims), &Reference::new(dims), 7);
+}
+
+#[rstest]
+#[case(7, 6, 4, false)]
+#[case(7, 6, 4, true)]
+#[case(9, 6, 5, false)]
+#[case(8, 7, 3, true)]
+#[case(9, 7, 5, true)]
+#[case(6, 5, 4, true)]
+fn test_random_games_match_reference(#[case] width: i32, #[case] height: i32, #[case] connect: i32, #[case] popout: bool) {
+    let dims = Dimensions::new(width, height).unwrap().with_connect(connect).unwrap();
+    let reference = Reference::new(dims);
+    let mut state = 0x9E37_79B9_7F4A_7C15;
+    for _ in 0..200 {
+        let mut game = Game::with_dimensions(dims);
+        if popout {
+            game.rules = Arc::new(PopOut);
+        }
+        // pops can go on forever, so the games are cut after a few times the size of the board
+        while !game.outcome.is_over() && game.board.history.len() < 3 * dims.squares() as usize {
+            play_random_move(&mut game, &mut state);
+            reference.check(&game);
+        }
+    }
+}
+
+#[rstest]
+#[case(7, 6, 4)]
+#[case(8, 7, 5)]
+#[case(9, 6, 4)]
+#[case(9, 7, 4)]
+#[case(1, 64, 5)]
+#[case(3, 3, 2)]
+fn test_random_bitboards_match_reference(#[case] width: i32, #[case] height: i32, #[case] connect: i32) {
+    let dims = Dimensions::new(width, height).unwrap().with_connect(connect).unwrap();
+    let game = Game::with_dimensions(dims);
+    let reference = Reference::new(dims);
+    let mut state = 0x2545_F491_4F6C_DD1D;
+    for _ in 0..2000 {
+        // dense boards, so that alignments are common and cross every edge
+        let tokens = (random_bitboard(&mut state) | random_bitboard(&mut state)) & dims.full();
+        assert_eq!(game.aligned_squares(tokens), reference.aligned(tokens), "{:#x}", tokens);
+    }
+}
@@ -0,0 +1,12 @@
+use puissance_4::api::engine::{set_zobrist_seed, Game};
+
+// kept alone in its own test binary, the seed must be set before any other game is created
+#[test]
+fn test_custom_zobrist_seed() {
+    assert!(set_zobrist_seed(42));
+    assert!(!set_zobrist_seed(43));
+
+    let mut game = Game::new();
+    game.make_push_bulk("4");
+    assert_ne!(game.zobrist_key, 0x6a0398e528f0ae6a);
+}